@@ -0,0 +1,54 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn chords_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/chords.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // chord
+    api.write_action(KeyAction::new(*KEY_J, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_K, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_K, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 0)).await?;
+    sleep(100);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_ESC, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_ESC, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // a single key is replayed once it's released
+    api.write_action(KeyAction::new(*KEY_J, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 0)).await?;
+    sleep(100);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_J, 1).to_input_ev(),
+        KeyAction::new(*KEY_J, 0).to_input_ev(),
+    ]);
+
+    // a single key is replayed once the chord window expires
+    api.write_action(KeyAction::new(*KEY_K, 1)).await?;
+    sleep(100);
+
+    let output_ev = api.collect_output_ev().await;
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_K, 1).to_input_ev(),
+    ]);
+
+    api.write_action(KeyAction::new(*KEY_K, 0)).await?;
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn closures_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/closures.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+    sleep(100);
+
+    let expected = indoc! {"
+    first: 3, second: 1
+    callback x
+    callback y
+    callback 0
+    callback 1
+    "};
+    assert_eq!(&*api.collect_stdout().await, expected);
+
+    for _ in 0..2 {
+        api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+        api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+        sleep(50);
+    }
+
+    let expected = indoc! {"
+    a pressed 1 times
+    a pressed 2 times
+    "};
+    assert_eq!(&*api.collect_stdout().await, expected);
+
+    api.stop().await;
+
+    Ok(())
+}
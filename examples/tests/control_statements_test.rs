@@ -18,6 +18,8 @@ async fn control_statements_test() -> Result<()> {
     i is 1
     i is 2
     i is 4
+    j is 1
+    j is 2
     "};
     assert_eq!(&*output, expected);
 
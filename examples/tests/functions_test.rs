@@ -16,6 +16,7 @@ async fn functions_test() -> Result<()> {
     hello world
     hello from my_function
     1 + 2 = 3
+    5! = 120
     "};
     assert_eq!(&*output, expected);
 
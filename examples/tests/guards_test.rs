@@ -0,0 +1,76 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn guards_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/guards.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // no guard applies
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_C, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_C, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // the guard is evaluated when the key is pressed
+    api.write_action(KeyAction::new(*KEY_F5, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_F5, 0)).await?;
+    sleep(50);
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // the release goes to the mapping that handled the press, even if the guard changed in between
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    sleep(20);
+    api.write_action(KeyAction::new(*KEY_F5, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_F5, 0)).await?;
+    sleep(20);
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // no window is focused, so the key is passed through
+    api.write_action(KeyAction::new(*KEY_X, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_X, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_X, 1).to_input_ev(),
+        KeyAction::new(*KEY_X, 0).to_input_ev(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
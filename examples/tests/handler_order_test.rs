@@ -0,0 +1,32 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn handler_order_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/handler-order.m2";
+
+    let mut api = test_script(params).await?;
+    sleep(50);
+
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(150);
+
+    api.write_action(KeyAction::new(*KEY_B, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_B, 0)).await?;
+    sleep(150);
+
+    let expected = indoc! {"
+    a down
+    a up
+    b up
+    b down
+    "};
+    assert_eq!(&*api.collect_stdout().await, expected);
+
+    api.stop().await;
+
+    Ok(())
+}
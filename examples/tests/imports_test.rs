@@ -0,0 +1,49 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn imports_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/imports.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 0)).await?;
+    sleep(50);
+    assert_eq!(api.collect_output_ev().await, vec![
+        KeyAction::new(*KEY_ESC, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_ESC, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(50);
+
+    // lambdas of the imported file keep using its variables
+    let expected = indoc! {"
+    hello a
+    hi hello
+    "};
+    assert_eq!(&*api.collect_stdout().await, expected);
+
+    for _ in 0..2 {
+        api.write_action(KeyAction::new(*KEY_B, 1)).await?;
+        api.write_action(KeyAction::new(*KEY_B, 0)).await?;
+    }
+    sleep(50);
+
+    let expected = indoc! {"
+    presses: 1
+    presses: 2
+    "};
+    assert_eq!(&*api.collect_stdout().await, expected);
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use crate::*;
+use crate::tests::*;
+
+fn key_events(events: Vec<InputEvent>) -> Vec<InputEvent> {
+    events.into_iter().filter(|ev| matches!(ev.event_code, EventCode::EV_KEY(_))).collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn key_repeat_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/key-repeat.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // software repeat presses the target again, the kernel's repeats are dropped
+    api.write_action(KeyAction::new(*KEY_H, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 2)).await?;
+    sleep(340);
+    api.write_action(KeyAction::new(*KEY_H, 0)).await?;
+    sleep(100);
+
+    let output_ev = key_events(api.collect_output_ev().await);
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT, 0).to_input_ev(),
+    ]);
+
+    // the sequence runs again on the kernel's repeat
+    api.write_action(KeyAction::new(*KEY_J, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 2)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 0)).await?;
+    sleep(100);
+
+    let output_ev = key_events(api.collect_output_ev().await);
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_A, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 0).to_input_ev(),
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+        KeyAction::new(*KEY_A, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 0).to_input_ev(),
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+    ]);
+
+    // suppressed repeats
+    api.write_action(KeyAction::new(*KEY_K, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_K, 2)).await?;
+    api.write_action(KeyAction::new(*KEY_K, 0)).await?;
+    sleep(100);
+
+    let output_ev = key_events(api.collect_output_ev().await);
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_K, 1).to_input_ev(),
+        KeyAction::new(*KEY_K, 0).to_input_ev(),
+    ]);
+
+    // the guard of the mapping with the software repeat doesn't hold
+    api.write_action(KeyAction::new(*KEY_L, 1)).await?;
+    sleep(340);
+    api.write_action(KeyAction::new(*KEY_L, 0)).await?;
+    sleep(100);
+
+    let output_ev = key_events(api.collect_output_ev().await);
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_RIGHT, 1).to_input_ev(),
+        KeyAction::new(*KEY_RIGHT, 0).to_input_ev(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
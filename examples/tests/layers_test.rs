@@ -0,0 +1,61 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn layers_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/layers.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // keys fall through to pass-through while no layer is active
+    api.write_action(KeyAction::new(*KEY_H, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_H, 1).to_input_ev(),
+        KeyAction::new(*KEY_H, 0).to_input_ev(),
+    ]);
+
+    // momentary layer
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_J, 1).to_input_ev(),
+        KeyAction::new(*KEY_J, 0).to_input_ev(),
+    ]);
+
+    // a key pressed before the layer was activated is released where it was pressed
+    api.write_action(KeyAction::new(*KEY_H, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_H, 1).to_input_ev(),
+        KeyAction::new(*KEY_H, 0).to_input_ev(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn lists_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/lists.m2";
+
+    let mut api = test_script(params).await.unwrap();
+    sleep(200);
+
+    let output = api.collect_stdout().await;
+
+    let expected = indoc! {r#"
+    there are 3 classes, the first one is firefox
+    firefox
+    kitty
+    Thunderbird
+    removed Thunderbird, remaining: ["firefox", "kitty"]
+    "#};
+    assert_eq!(&*output, expected);
+
+    api.stop().await;
+
+    Ok(())
+}
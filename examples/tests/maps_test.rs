@@ -0,0 +1,27 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn maps_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/maps.m2";
+
+    let mut api = test_script(params).await.unwrap();
+    sleep(200);
+
+    let output = api.collect_stdout().await;
+
+    let expected = indoc! {r#"
+    firefox opens a new tab with ^t
+    Thunderbird: ^n
+    firefox: ^t
+    kitty: ^+t
+    remaining classes: ["Thunderbird", "firefox"]
+    "#};
+    assert_eq!(&*output, expected);
+
+    api.stop().await;
+
+    Ok(())
+}
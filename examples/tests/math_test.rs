@@ -18,6 +18,11 @@ async fn math_test() -> Result<()> {
     2 times 4 is: 8
     4 divided by 2 is: 2
     result of complicated calculation: 3.8
+    7 modulo 3 is: 1
+    negated: -1
+    1 + 2 * 3 is: 7
+    2 <= 2 is: true, 1 >= 2 is: false
+    check was not called
     "};
     assert_eq!(&*output, expected);
 
@@ -1,4 +1,25 @@
 mod functions_test;
 mod math_test;
 mod hjkl_arrow_keys_test;
-mod control_statements_test;
\ No newline at end of file
+mod control_statements_test;
+mod tap_hold_test;
+mod chords_test;
+mod sequences_test;
+mod layers_test;
+mod sticky_modifiers_test;
+mod side_specific_modifiers_test;
+mod wildcard_modifiers_test;
+mod guards_test;
+mod when_window_test;
+mod unmap_test;
+mod lists_test;
+mod maps_test;
+mod strings_test;
+mod runtime_errors_test;
+mod imports_test;
+mod closures_test;
+mod timers_test;
+mod tasks_test;
+mod handler_order_test;
+mod key_repeat_test;
+mod mouse_test;
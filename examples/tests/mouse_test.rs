@@ -0,0 +1,73 @@
+use evdev_rs::enums::{EV_KEY, EV_REL};
+
+use crate::*;
+use crate::tests::*;
+
+fn rel(axis: EV_REL, value: i32) -> InputEvent {
+    InputEvent { event_code: EventCode::EV_REL(axis), value, time: INPUT_EV_DUMMY_TIME }
+}
+
+fn key(key: EV_KEY, value: i32) -> InputEvent {
+    KeyAction::new(Key { event_code: EventCode::EV_KEY(key) }, value).to_input_ev()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn mouse_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/mouse.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // the tilt wheel is mapped to key clicks
+    api.write_event(rel(EV_REL::REL_HWHEEL, -1)).await?;
+    sleep(50);
+
+    let output_ev: Vec<InputEvent> = api.collect_output_ev().await.into_iter().filter(|ev| ev.event_code != SYN_REPORT.event_code).collect();
+    assert_eq!(output_ev, vec![
+        key(EV_KEY::KEY_LEFTCTRL, 1),
+        key(EV_KEY::KEY_LEFTALT, 1),
+        key(EV_KEY::KEY_LEFT, 1),
+        key(EV_KEY::KEY_LEFT, 0),
+        key(EV_KEY::KEY_LEFTCTRL, 0),
+        key(EV_KEY::KEY_LEFTALT, 0),
+    ]);
+
+    // the wheel scrolls unless the side button is held, events that are passed on after their
+    // guard was checked are reported in a frame of their own
+    api.write_event(rel(EV_REL::REL_WHEEL_HI_RES, 120)).await?;
+    api.write_event(rel(EV_REL::REL_WHEEL, 1)).await?;
+    sleep(50);
+    assert_eq!(api.collect_output_ev().await, vec![
+        rel(EV_REL::REL_WHEEL_HI_RES, 120),
+        SYN_REPORT.clone(),
+        rel(EV_REL::REL_WHEEL, 1),
+        SYN_REPORT.clone(),
+    ]);
+
+    api.write_event(key(EV_KEY::BTN_SIDE, 1)).await?;
+    api.write_event(rel(EV_REL::REL_WHEEL_HI_RES, 120)).await?;
+    api.write_event(rel(EV_REL::REL_WHEEL, 1)).await?;
+    api.write_event(key(EV_KEY::BTN_SIDE, 0)).await?;
+    sleep(50);
+
+    let output_ev: Vec<InputEvent> = api.collect_output_ev().await.into_iter().filter(|ev| ev.event_code != SYN_REPORT.event_code).collect();
+    assert_eq!(output_ev, vec![
+        key(EV_KEY::KEY_VOLUMEUP, 1),
+        key(EV_KEY::KEY_VOLUMEUP, 0),
+    ]);
+
+    // mouse movement is transformed
+    api.write_event(rel(EV_REL::REL_X, -3)).await?;
+    api.write_event(rel(EV_REL::REL_Y, 4)).await?;
+    sleep(50);
+    assert_eq!(api.collect_output_ev().await, vec![
+        rel(EV_REL::REL_X, -6),
+        SYN_REPORT.clone(),
+        rel(EV_REL::REL_Y, 4),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
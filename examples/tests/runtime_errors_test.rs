@@ -0,0 +1,62 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn runtime_errors_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/runtime-errors.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // the mapping keeps working after it failed
+    for _ in 0..2 {
+        api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+        api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+        sleep(50);
+    }
+
+    let output = api.collect_stdout().await;
+
+    let expected = indoc! {"
+    pressed a 1 times
+    pressed a 2 times
+    "};
+    assert_eq!(&*output, expected);
+
+    let errors = api.collect_stderr().await;
+
+    let expected = indoc! {"
+    error: examples/runtime-errors.m2:11:3: cannot subtract number from string
+    error: examples/runtime-errors.m2:11:3: cannot subtract number from string
+    "};
+    assert_eq!(&*errors, expected);
+    assert_eq!(api.exit_code().await, None);
+
+    api.stop().await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn runtime_errors_exit_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/runtime-errors.m2";
+    params.on_error = Some(ErrorPolicy::Exit);
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // the script stops processing events after the error, so the release can't be written
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    sleep(50);
+
+    assert_eq!(api.collect_stdout().await, "pressed a 1 times\n");
+    assert_eq!(api.collect_stderr().await, "error: examples/runtime-errors.m2:11:3: cannot subtract number from string\n");
+    assert_eq!(api.exit_code().await, Some(1));
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sequences_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/sequences.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // sequence
+    api.write_action(KeyAction::new(*KEY_G, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_G, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_G, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_G, 0)).await?;
+    sleep(100);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_HOME, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_HOME, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // a broken sequence is replayed in the original order
+    api.write_action(KeyAction::new(*KEY_G, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_G, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(100);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_G, 1).to_input_ev(),
+        KeyAction::new(*KEY_G, 0).to_input_ev(),
+        KeyAction::new(*KEY_A, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 0).to_input_ev(),
+    ]);
+
+    // modifiers pressed during a sequence are replayed in the original order
+    api.write_action(KeyAction::new(*KEY_G, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_G, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 0)).await?;
+    sleep(100);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_G, 1).to_input_ev(),
+        KeyAction::new(*KEY_G, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 0).to_input_ev(),
+    ]);
+
+    // an incomplete sequence is replayed once the timeout expires
+    api.write_action(KeyAction::new(*KEY_G, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_G, 0)).await?;
+    sleep(500);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_G, 1).to_input_ev(),
+        KeyAction::new(*KEY_G, 0).to_input_ev(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
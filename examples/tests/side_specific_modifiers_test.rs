@@ -0,0 +1,79 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn side_specific_modifiers_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/side-specific-modifiers.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // left alt
+    api.write_action(KeyAction::new(*KEY_LEFT_ALT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_ALT, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_ALT, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_ALT, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT_ALT, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_ALT, 0).to_input_ev(),
+    ]);
+
+    // right alt
+    api.write_action(KeyAction::new(*KEY_RIGHT_ALT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_H, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_RIGHT_ALT, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_RIGHT_ALT, 1).to_input_ev(),
+        KeyAction::new(*KEY_RIGHT_ALT, 0).to_input_ev(),
+        KeyAction::new(*KEY_HOME, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_HOME, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_RIGHT_ALT, 1).to_input_ev(),
+        KeyAction::new(*KEY_RIGHT_ALT, 0).to_input_ev(),
+    ]);
+
+    // right-hand modifier in the output
+    api.write_action(KeyAction::new(*KEY_LEFT_CTRL, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_CTRL, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_CTRL, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_CTRL, 0).to_input_ev(),
+        KeyAction::new(*KEY_RIGHT_CTRL, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_RIGHT_CTRL, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT_CTRL, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_CTRL, 0).to_input_ev(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
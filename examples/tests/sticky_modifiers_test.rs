@@ -0,0 +1,75 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sticky_modifiers_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/sticky-modifiers.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // one-shot modifier
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_B, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_B, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_SHIFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+    ]);
+
+    // locked modifier
+    for _ in 0..2 {
+        api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 1)).await?;
+        api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 0)).await?;
+    }
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_SHIFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 0).to_input_ev(),
+    ]);
+
+    // one-shot modifier on a mapped key
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_SHIFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_D, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_D, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
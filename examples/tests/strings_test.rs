@@ -0,0 +1,27 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn strings_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/strings.m2";
+
+    let mut api = test_script(params).await.unwrap();
+    sleep(200);
+
+    let output = api.collect_stdout().await;
+
+    let expected = indoc! {r#"
+    sum: 5
+    she said "hi"	and left
+    this is not interpolated: ${a}
+    C:\Users\me
+    raw strings can contain "quotes" too
+    "#};
+    assert_eq!(&*output, expected);
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tap_hold_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/tap-hold.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(50);
+
+    // tap
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_ESC, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_ESC, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // hold while pressing another key
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_CTRL, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_C, 1).to_input_ev(),
+        KeyAction::new(*KEY_C, 0).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_CTRL, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // hold past the tapping term
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 1)).await?;
+    sleep(300);
+    let output_ev = api.collect_output_ev().await;
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_CTRL, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    api.write_action(KeyAction::new(*KEY_CAPSLOCK, 0)).await?;
+    sleep(50);
+    let output_ev = api.collect_output_ev().await;
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_CTRL, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::*;
+use crate::tests::*;
+use indoc::indoc;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tasks_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/tasks.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+    sleep(50);
+
+    api.write_action(KeyAction::new(*KEY_J, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 0)).await?;
+    sleep(250);
+    api.write_action(KeyAction::new(*KEY_K, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_K, 0)).await?;
+    sleep(50);
+
+    let output = api.collect_stdout().await;
+    let steps = output.matches("macro step").count();
+    assert!((2..=4).contains(&steps), "{}", output);
+    assert!(output.ends_with("macro stopped\n"), "{}", output);
+
+    // the macro doesn't continue after it was cancelled
+    sleep(250);
+    assert_eq!(&*api.collect_stdout().await, "");
+
+    for _ in 0..2 {
+        api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+        api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    }
+    api.write_action(KeyAction::new(*KEY_B, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_B, 0)).await?;
+    sleep(50);
+
+    let expected = indoc! {"
+    received a
+    received a
+    logger handled 2 presses
+    "};
+    assert_eq!(&*api.collect_stdout().await, expected);
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn timers_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/timers.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // pressing 'a' restarts the idle timeout
+    sleep(100);
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(150);
+    assert_eq!(&*api.collect_stdout().await, "");
+    sleep(200);
+    assert_eq!(&*api.collect_stdout().await, "idle\n");
+
+    api.write_action(KeyAction::new(*KEY_J, 1)).await?;
+    sleep(350);
+    api.write_action(KeyAction::new(*KEY_J, 0)).await?;
+    sleep(50);
+    let scrolled = api.collect_stdout().await.matches("scroll").count();
+    assert!((2..=4).contains(&scrolled), "scrolled {} times", scrolled);
+
+    // the interval stops after it was cleared
+    sleep(250);
+    assert_eq!(&*api.collect_stdout().await, "");
+
+    api.stop().await;
+
+    Ok(())
+}
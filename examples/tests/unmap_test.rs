@@ -0,0 +1,92 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn unmap_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/unmap.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_D, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_D, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+    ]);
+
+    // unmap by trigger, a key that is held down is still released through the removed mapping
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    sleep(50);
+    api.write_action(KeyAction::new(*KEY_F5, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_F5, 0)).await?;
+    sleep(50);
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    sleep(50);
+
+    assert_eq!(api.collect_stdout().await, "[\"c\", \"f3\", \"f4\", \"f5\"]\n");
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_A, 1).to_input_ev(),
+        KeyAction::new(*KEY_A, 0).to_input_ev(),
+    ]);
+
+    // unmap by handle
+    api.write_action(KeyAction::new(*KEY_F4, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_F4, 0)).await?;
+    sleep(50);
+    api.write_action(KeyAction::new(*KEY_C, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_C, 1).to_input_ev(),
+        KeyAction::new(*KEY_C, 0).to_input_ev(),
+    ]);
+
+    // unmap a chord by handle
+    api.write_action(KeyAction::new(*KEY_F3, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_F3, 0)).await?;
+    sleep(50);
+    api.write_action(KeyAction::new(*KEY_J, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_K, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_K, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_J, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_J, 1).to_input_ev(),
+        KeyAction::new(*KEY_K, 1).to_input_ev(),
+        KeyAction::new(*KEY_K, 0).to_input_ev(),
+        KeyAction::new(*KEY_J, 0).to_input_ev(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
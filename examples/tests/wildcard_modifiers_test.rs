@@ -0,0 +1,73 @@
+use crate::*;
+use crate::tests::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wildcard_modifiers_test() -> Result<()> {
+    let mut params = ScriptTestingParameters::default();
+    params.script_path = "examples/wildcard-modifiers.m2";
+
+    let mut api = test_script(params).await?;
+    api.event_delay = Some(10);
+
+    // extra modifiers are released
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_SHIFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 0).to_input_ev(),
+        KeyAction::new(*KEY_B, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_B, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 0).to_input_ev(),
+    ]);
+
+    // extra modifiers are passed through
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_C, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_SHIFT, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_SHIFT, 1).to_input_ev(),
+        KeyAction::new(*KEY_D, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_D, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT_SHIFT, 0).to_input_ev(),
+    ]);
+
+    // exact match
+    api.write_action(KeyAction::new(*KEY_LEFT_CTRL, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 1)).await?;
+    api.write_action(KeyAction::new(*KEY_A, 0)).await?;
+    api.write_action(KeyAction::new(*KEY_LEFT_CTRL, 0)).await?;
+    sleep(50);
+
+    let output_ev = api.collect_output_ev().await;
+
+    assert_eq!(output_ev, vec![
+        KeyAction::new(*KEY_LEFT_CTRL, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_CTRL, 0).to_input_ev(),
+        KeyAction::new(*KEY_X, 1).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_X, 0).to_input_ev(),
+        SYN_REPORT.clone(),
+        KeyAction::new(*KEY_LEFT_CTRL, 1).to_input_ev(),
+        KeyAction::new(*KEY_LEFT_CTRL, 0).to_input_ev(),
+    ]);
+
+    api.stop().await;
+
+    Ok(())
+}
@@ -3,6 +3,14 @@ use crate::*;
 impl Block {
     pub(crate) fn push_expr(&mut self, expr: Expr) -> &mut Self {
         self.statements.push(Stmt::Expr(expr));
+        if !self.spans.is_empty() { self.spans.push(Span::default()); }
+        self
+    }
+
+    /// Inserts a generated statement, the locations of the parsed statements are kept.
+    pub(crate) fn insert_expr(&mut self, idx: usize, expr: Expr) -> &mut Self {
+        self.statements.insert(idx, Stmt::Expr(expr));
+        if !self.spans.is_empty() { self.spans.insert(idx, Span::default()); }
         self
     }
 }
@@ -10,7 +18,7 @@ impl Block {
 impl Expr {
 
     pub(crate) fn map_key_click_block(from: KeyClickActionWithMods, mut to: Block) -> Self {
-        to.statements.insert(0, Stmt::Expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), KeyModifierFlags::new(), TYPE_UP)));
+        to.insert_expr(0, Expr::ReleaseRestoreModifiers(from.modifiers.clone(), KeyModifierFlags::new(), TYPE_UP));
         Expr::KeyMapping(vec![
             KeyMapping { from: KeyActionWithMods::new(from.key, TYPE_DOWN, from.modifiers), to },
             KeyMapping { from: KeyActionWithMods::new(from.key, TYPE_REPEAT, from.modifiers), to: Block::new() }, // stub
@@ -19,7 +27,7 @@ impl Expr {
     }
 
     pub(crate) fn map_key_block(from: KeyActionWithMods, mut to: Block) -> Self {
-        to.statements.insert(0, Stmt::Expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), KeyModifierFlags::new(), TYPE_UP)));
+        to.insert_expr(0, Expr::ReleaseRestoreModifiers(from.modifiers.clone(), KeyModifierFlags::new(), TYPE_UP));
 
         Expr::KeyMapping(vec![KeyMapping { from, to }])
     }
@@ -29,18 +37,18 @@ impl Expr {
 
         block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_UP));
 
-        if !from.modifiers.ctrl && to.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_DOWN })); }
-        if !from.modifiers.alt && to.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_DOWN })); }
-        if !from.modifiers.shift && to.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_DOWN })); }
-        if !from.modifiers.meta && to.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_DOWN })); }
 
         block.push_expr(Expr::KeyAction(KeyAction { key: to.key, value: to.value }));
 
         // revert to original
-        if !from.modifiers.ctrl && to.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_UP })); }
-        if !from.modifiers.alt && to.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_UP })); }
-        if !from.modifiers.shift && to.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_UP })); }
-        if !from.modifiers.meta && to.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_UP })); }
 
         block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_DOWN));
 
@@ -53,18 +61,18 @@ impl Expr {
 
         block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_UP));
 
-        if !from.modifiers.ctrl && to.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_DOWN })); }
-        if !from.modifiers.alt && to.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_DOWN })); }
-        if !from.modifiers.shift && to.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_DOWN })); }
-        if !from.modifiers.meta && to.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_DOWN })); }
 
         block.push_expr(Expr::KeyAction(KeyAction { key: to.key, value: to.value }));
 
         // revert to original
-        if !from.modifiers.ctrl && to.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_UP })); }
-        if !from.modifiers.alt && to.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_UP })); }
-        if !from.modifiers.shift && to.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_UP })); }
-        if !from.modifiers.meta && to.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_UP })); }
 
         block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_DOWN));
 
@@ -80,19 +88,19 @@ impl Expr {
 
         block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_UP));
 
-        if !from.modifiers.ctrl && to.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_DOWN })); }
-        if !from.modifiers.alt && to.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_DOWN })); }
-        if !from.modifiers.shift && to.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_DOWN })); }
-        if !from.modifiers.meta && to.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_DOWN })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_DOWN })); }
 
         block.push_expr(Expr::KeyAction(KeyAction { key: to.key, value: TYPE_DOWN }));
         block.push_expr(Expr::KeyAction(KeyAction { key: to.key, value: TYPE_UP }));
 
         // revert to original
-        if !from.modifiers.ctrl && to.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_UP })); }
-        if !from.modifiers.alt && to.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_UP })); }
-        if !from.modifiers.shift && to.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_UP })); }
-        if !from.modifiers.meta && to.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_UP })); }
+        if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_UP })); }
 
         block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_DOWN));
 
@@ -105,10 +113,10 @@ impl Expr {
             let mut block = Block::new();
             block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_UP));
 
-            if to.modifiers.ctrl && !from.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_DOWN })); }
-            if to.modifiers.alt && !from.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_DOWN })); }
-            if to.modifiers.shift && !from.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_DOWN })); }
-            if to.modifiers.meta && !from.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_DOWN })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_DOWN })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_DOWN })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_DOWN })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_DOWN })); }
 
             block.push_expr(Expr::KeyAction(KeyAction { key: to.key, value: TYPE_DOWN }));
 
@@ -119,10 +127,10 @@ impl Expr {
             let mut block = Block::new();
             block.push_expr(Expr::KeyAction(KeyAction { key: to.key, value: TYPE_UP }));
 
-            if to.modifiers.ctrl && !from.modifiers.ctrl { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_CTRL, value: TYPE_UP })); }
-            if to.modifiers.alt && !from.modifiers.alt { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_ALT, value: TYPE_UP })); }
-            if to.modifiers.shift && !from.modifiers.shift { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_SHIFT, value: TYPE_UP })); }
-            if to.modifiers.meta && !from.modifiers.meta { block.push_expr(Expr::KeyAction(KeyAction { key: *KEY_LEFT_META, value: TYPE_UP })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Ctrl) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.ctrl_key(), value: TYPE_UP })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Alt) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.alt_key(), value: TYPE_UP })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Shift) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.shift_key(), value: TYPE_UP })); }
+            if from.modifiers.needs_press(&to.modifiers, Modifier::Meta) { block.push_expr(Expr::KeyAction(KeyAction { key: to.modifiers.meta_key(), value: TYPE_UP })); }
 
             block.push_expr(Expr::ReleaseRestoreModifiers(from.modifiers.clone(), to.modifiers.clone(), TYPE_DOWN));
 
@@ -137,6 +145,36 @@ impl Expr {
         }
         Expr::KeyMapping(mappings)
     }
+
+    /// Sets what holding the key of a click mapping does, returns `None` if the mapping isn't
+    /// triggered by a click. Repeating the target releases and presses it again, so mapped clicks
+    /// stay held down in between and blocks run once more.
+    pub(crate) fn with_key_repeat(self, repeat: &KeyRepeat) -> Option<Self> {
+        let mut mappings = match self {
+            Expr::KeyMapping(mappings) => mappings,
+            _ => return None,
+        };
+
+        let block_of = |mappings: &Vec<KeyMapping>, value: i32| mappings.iter()
+            .find(|v| v.from.value == value)
+            .map(|v| v.to.clone());
+        let (down, up) = (block_of(&mappings, TYPE_DOWN)?, block_of(&mappings, TYPE_UP)?);
+        let repeat_mapping = mappings.iter_mut().find(|v| v.from.value == TYPE_REPEAT)?;
+
+        let mut press_again = up;
+        if !down.spans.is_empty() { press_again.spans.resize(press_again.statements.len(), Span::default()); }
+        press_again.statements.extend(down.statements);
+        press_again.spans.extend(down.spans);
+
+        repeat_mapping.to = match repeat {
+            // clicks already pass the repeat on to the target key
+            KeyRepeat::Pass if !repeat_mapping.to.statements.is_empty() => repeat_mapping.to.clone(),
+            KeyRepeat::Pass | KeyRepeat::Software(_) => press_again,
+            KeyRepeat::Suppress => Block::new(),
+        };
+
+        Some(Expr::KeyMapping(mappings))
+    }
 }
 
 pub(crate) trait ExprVecExt {
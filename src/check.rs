@@ -0,0 +1,330 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::*;
+use crate::parsing::parser::{invalid_key_actions, parse_key_sequence, parse_script};
+use crate::import::resolve_imports;
+use crate::runtime::builtin_arity;
+
+/// A problem found by statically checking a script, located at the statement that contains it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
+
+/// Parses and checks a script along with its imports without evaluating it, fails if the script
+/// can't be parsed.
+pub fn check_script(raw_script: &str, file_name: &str) -> Result<Vec<Problem>> {
+    let block = parse_script(raw_script, file_name)?;
+    let block = resolve_imports(block, Path::new(file_name))?;
+
+    let mut checker = Checker { problems: vec![], scopes: vec![] };
+    checker.check_block(&block, vec![]);
+    Ok(checker.problems)
+}
+
+/// Adds the namespace and the qualified names of the module's variables to the scope.
+fn module_names(namespace: &str, module: &Block, scope: &mut HashSet<String>) {
+    scope.insert(namespace.to_string());
+    for stmt in &module.statements {
+        match stmt {
+            Stmt::Expr(Expr::Init(name, _)) | Stmt::Function(name, _, _) => {
+                scope.insert(format!("{}.{}", namespace, name));
+            }
+            Stmt::Namespace(nested, module) => module_names(&format!("{}.{}", namespace, nested), module, scope),
+            _ => {}
+        }
+    }
+}
+
+struct Checker {
+    problems: Vec<Problem>,
+    /// the variables that are visible, one set per nested block
+    scopes: Vec<HashSet<String>>,
+}
+
+impl Checker {
+    fn report(&mut self, span: &Span, message: String) {
+        self.problems.push(Problem { span: span.clone(), message });
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        if self.scopes.iter().any(|scope| scope.contains(name)) { return true; }
+
+        // the variables of a module that's bound to another name aren't known
+        match name.rsplit_once('.') {
+            Some((namespace, _)) => {
+                let prefix = format!("{}.", namespace);
+                self.is_defined(namespace) &&
+                    !self.scopes.iter().flatten().any(|defined| defined.starts_with(&prefix))
+            }
+            None => false,
+        }
+    }
+
+    fn check_block(&mut self, block: &Block, params: Vec<String>) {
+        // variables can be used before their definition by functions that are called later on, so
+        // every variable of a block is visible in the whole block
+        let mut scope: HashSet<String> = params.into_iter().collect();
+        for stmt in &block.statements {
+            match stmt {
+                Stmt::Expr(Expr::Init(name, _)) | Stmt::For(Expr::Init(name, _), _, _, _) |
+                Stmt::Function(name, _, _) => { scope.insert(name.clone()); }
+                Stmt::Namespace(namespace, module) => module_names(namespace, module, &mut scope),
+                _ => {}
+            }
+        }
+        self.scopes.push(scope);
+
+        let mut terminated = false;
+        let mut mapped: Vec<(KeyActionWithMods, Option<&Expr>, Span)> = vec![];
+
+        for (idx, stmt) in block.statements.iter().enumerate() {
+            let span = block.span(idx);
+
+            if let Some((mappings, guard)) = mapping_of(stmt) {
+                if terminated {
+                    self.report(&span, "unreachable mapping".to_string());
+                }
+                if let Some(Expr::Value(ValueType::Bool(false))) = guard {
+                    self.report(&span, "unreachable mapping, the guard is always false".to_string());
+                }
+
+                // re-mapping a trigger with the same guard replaces the earlier mapping
+                let mut shadowed: Vec<(Span, String)> = vec![];
+                for mapping in mappings {
+                    while let Some(pos) = mapped.iter().position(|(from, other_guard, _)| *from == mapping.from && *other_guard == guard) {
+                        let (from, _, shadowed_span) = mapped.remove(pos);
+                        if !shadowed.iter().any(|(v, _)| *v == shadowed_span) {
+                            shadowed.push((shadowed_span, format!("{}{}", from.modifiers, from.key)));
+                        }
+                    }
+                    mapped.push((mapping.from.clone(), guard, span.clone()));
+                }
+                for (shadowed_span, trigger) in shadowed {
+                    self.report(&shadowed_span, format!("mapping for '{}' is shadowed by a later mapping at {}", trigger, span));
+                }
+            }
+
+            self.check_stmt(stmt, &span);
+
+            match stmt {
+                Stmt::Return(_) | Stmt::Break | Stmt::Continue => terminated = true,
+                Stmt::Expr(Expr::FunctionCall(name, _)) if name == "exit" => terminated = true,
+                _ => {}
+            }
+        }
+
+        self.scopes.pop();
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt, span: &Span) {
+        match stmt {
+            Stmt::Expr(expr) | Stmt::Return(expr) => self.check_expr(expr, span),
+            Stmt::Block(block) | Stmt::WhenWindow(_, block) | Stmt::Namespace(_, block) => self.check_block(block, vec![]),
+            Stmt::Function(_, params, block) => self.check_block(block, params.clone()),
+            Stmt::Import(_, _) => self.report(span, "imports are only allowed at the top level of a script".to_string()),
+            Stmt::If(pairs, else_block) => {
+                for (condition, block) in pairs {
+                    self.check_expr(condition, span);
+                    self.check_block(block, vec![]);
+                }
+                if let Some(block) = else_block { self.check_block(block, vec![]); }
+            }
+            Stmt::For(init, condition, advance, block) => {
+                self.check_expr(init, span);
+                self.check_expr(condition, span);
+                self.check_expr(advance, span);
+                self.check_block(block, vec![]);
+            }
+            Stmt::ForIn(item, items, block) => {
+                self.check_expr(items, span);
+                self.check_block(block, vec![item.clone()]);
+            }
+            Stmt::While(condition, block) => {
+                self.check_expr(condition, span);
+                self.check_block(block, vec![]);
+            }
+            Stmt::Continue | Stmt::Break => {}
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr, span: &Span) {
+        match expr {
+            Expr::Eq(left, right) | Expr::Neq(left, right) | Expr::LT(left, right) | Expr::GT(left, right) |
+            Expr::LE(left, right) | Expr::GE(left, right) | Expr::Add(left, right) | Expr::Sub(left, right) |
+            Expr::Div(left, right) | Expr::Mul(left, right) | Expr::Mod(left, right) | Expr::And(left, right) |
+            Expr::Or(left, right) | Expr::Index(left, right) => {
+                self.check_expr(left, span);
+                self.check_expr(right, span);
+            }
+            Expr::Neg(value) | Expr::Minus(value) | Expr::Init(_, value) => self.check_expr(value, span),
+            Expr::Assign(name, value) => {
+                if !self.is_defined(name) {
+                    self.report(span, format!("undefined variable '{}'", name));
+                }
+                self.check_expr(value, span);
+            }
+            Expr::Name(name) => {
+                if !self.is_defined(name) {
+                    self.report(span, format!("undefined variable '{}'", name));
+                }
+            }
+            Expr::KeyMapping(mappings) => {
+                for mapping in mappings { self.check_block(&mapping.to, vec![]); }
+            }
+            Expr::GuardedKeyMapping(mappings, guard) => {
+                self.check_expr(guard, span);
+                for mapping in mappings { self.check_block(&mapping.to, vec![]); }
+            }
+            Expr::RepeatKeyMapping(mapping, _) => self.check_expr(mapping, span),
+            Expr::RelMapping(_, guard, block) => {
+                if let Some(guard) = guard { self.check_expr(guard, span); }
+                self.check_block(block, vec!["value".to_string()]);
+            }
+            Expr::ChordMapping(_, down, up) => {
+                self.check_block(down, vec![]);
+                self.check_block(up, vec![]);
+            }
+            Expr::SequenceMapping(_, block) => self.check_block(block, vec![]),
+            Expr::Lambda(params, block) => self.check_block(block, params.clone()),
+            Expr::List(items) => {
+                for item in items { self.check_expr(item, span); }
+            }
+            Expr::Map(items) => {
+                for (_, item) in items { self.check_expr(item, span); }
+            }
+            Expr::FunctionCall(name, args) => {
+                self.check_function_call(name, args, span);
+                for arg in args { self.check_expr(arg, span); }
+            }
+            Expr::TapHoldMapping(_, _) | Expr::Value(_) | Expr::KeyAction(_) | Expr::SleepAction(_) |
+            Expr::ReleaseRestoreModifiers(_, _, _) => {}
+        }
+    }
+
+    fn check_function_call(&mut self, name: &str, args: &[Expr], span: &Span) {
+        let (min, max) = match builtin_arity(name) {
+            Some(arity) => arity,
+            None => {
+                if !self.is_defined(name) {
+                    self.report(span, format!("undefined function '{}'", name));
+                }
+                return;
+            }
+        };
+
+        if args.len() < min || max.map_or(false, |max| args.len() > max) {
+            let expected = match max {
+                Some(max) if max == min => format!("{}", min),
+                Some(max) => format!("{} to {}", min, max),
+                None => format!("at least {}", min),
+            };
+            self.report(span, format!("function '{}' expects {} argument(s), got {}", name, expected, args.len()));
+        }
+
+        if name == "send" || name == "send_modifier" {
+            if let Some(Expr::Value(ValueType::String(seq))) = args.get(0) {
+                if parse_key_sequence(seq).is_err() {
+                    self.report(span, format!("invalid key sequence '{}'", seq));
+                }
+                for action in invalid_key_actions(seq) {
+                    self.report(span, format!("unknown key '{}' in key sequence, it is typed out literally", action));
+                }
+            }
+        }
+    }
+}
+
+/// The key mappings that a statement defines, along with their guard.
+fn mapping_of(stmt: &Stmt) -> Option<(&Vec<KeyMapping>, Option<&Expr>)> {
+    let expr = match stmt {
+        Stmt::Expr(Expr::Init(_, expr)) => expr.deref(),
+        Stmt::Expr(expr) => expr,
+        _ => return None,
+    };
+
+    let expr = match expr {
+        Expr::RepeatKeyMapping(mapping, _) => mapping.deref(),
+        expr => expr,
+    };
+
+    match expr {
+        Expr::KeyMapping(mappings) => Some((mappings, None)),
+        Expr::GuardedKeyMapping(mappings, guard) => Some((mappings, Some(guard.deref()))),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(script: &str) -> Vec<String> {
+        check_script(script, "script.m2").unwrap().into_iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_undefined_variables() {
+        assert_eq!(check("let a = 1;\nprint(a + b);\nc = 2;"), vec![
+            "script.m2:2:1: undefined variable 'b'",
+            "script.m2:3:1: undefined variable 'c'",
+        ]);
+
+        // functions may use variables that are defined after them
+        assert_eq!(check("let f = |x|{ print(x + y); };\nlet y = 2;\nf(1);"), Vec::<String>::new());
+        assert_eq!(check("for (item in [1]) { print(item); }\nfoo();"), vec![
+            "script.m2:2:1: undefined function 'foo'",
+        ]);
+        assert_eq!(check("bar(1);\nfn bar(n) { if (n > 0) { bar(n - 1); } print(m); }"), vec![
+            "script.m2:2:40: undefined variable 'm'",
+        ]);
+    }
+
+    #[test]
+    fn test_imports() {
+        let raw = fs::read_to_string("examples/imports.m2").unwrap();
+        assert_eq!(check_script(&raw, "examples/imports.m2").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_builtin_arity() {
+        assert_eq!(check("print();\nprint(1, 2);\nexecute(\"ls\", \"-l\");"), vec![
+            "script.m2:1:1: function 'print' expects 1 argument(s), got 0",
+            "script.m2:2:1: function 'print' expects 1 argument(s), got 2",
+        ]);
+    }
+
+    #[test]
+    fn test_key_sequences() {
+        assert_eq!(check("a::{\n  send(\"hello{entr}\");\n  send(\"\u{1F600}\");\n};"), vec![
+            "script.m2:2:3: unknown key '{entr}' in key sequence, it is typed out literally",
+            "script.m2:3:3: invalid key sequence '\u{1F600}'",
+        ]);
+    }
+
+    #[test]
+    fn test_mappings() {
+        assert_eq!(check("a::b;\nb::c;\na::c;"), vec![
+            "script.m2:1:1: mapping for 'a' is shadowed by a later mapping at script.m2:3:1",
+        ]);
+
+        // guarded mappings don't replace unguarded ones
+        assert_eq!(check("let mode = 1;\na::b if mode == 1;\na::c;"), Vec::<String>::new());
+
+        assert_eq!(check("a::b;\nexit();\nb::c;\nc::d if false;"), vec![
+            "script.m2:3:1: unreachable mapping",
+            "script.m2:4:1: unreachable mapping",
+            "script.m2:4:1: unreachable mapping, the guard is always false",
+        ]);
+    }
+}
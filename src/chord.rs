@@ -0,0 +1,269 @@
+use crate::*;
+
+pub const DEFAULT_CHORD_WINDOW: time::Duration = time::Duration::from_millis(50);
+
+/// A set of keys that trigger a mapping when pressed simultaneously.
+#[derive(Debug, Clone)]
+pub struct ChordMapping {
+    pub id: usize,
+    pub keys: Vec<Key>,
+    pub down: Block,
+    pub up: Block,
+    pub var_map: Environment,
+}
+
+struct ActiveChord {
+    held: Vec<Key>,
+    up: Option<(Block, Environment)>,
+}
+
+/// The result of feeding an event to the chord matcher.
+pub struct ChordOutcome {
+    /// blocks that should be run because a chord was completed or released, run before the replay
+    pub fire: Vec<(Block, Environment)>,
+    /// events that should continue being processed, in their original order
+    pub replay: Vec<InputEvent>,
+    /// a timer that should fire `ChordTimeout` with the given id after the duration
+    pub timer: Option<(usize, time::Duration)>,
+}
+
+impl ChordOutcome {
+    fn replay(replay: Vec<InputEvent>) -> Self { ChordOutcome { fire: vec![], replay, timer: None } }
+    fn fire(block: Block, var_map: Environment) -> Self { ChordOutcome { fire: vec![(block, var_map)], replay: vec![], timer: None } }
+    fn consumed() -> Self { ChordOutcome { fire: vec![], replay: vec![], timer: None } }
+
+    fn append(&mut self, mut other: ChordOutcome) {
+        self.fire.append(&mut other.fire);
+        self.replay.append(&mut other.replay);
+        if other.timer.is_some() { self.timer = other.timer; }
+    }
+}
+
+pub struct ChordState {
+    pub mappings: Vec<ChordMapping>,
+    pub window: time::Duration,
+    buffer: Vec<InputEvent>,
+    buffer_id: usize,
+    active: Vec<ActiveChord>,
+}
+
+fn is_subset(keys: &[Key], of: &[Key]) -> bool { keys.iter().all(|key| of.contains(key)) }
+
+fn is_same_set(left: &[Key], right: &[Key]) -> bool { left.len() == right.len() && is_subset(left, right) }
+
+/// Marks the chord as held down and returns its down block.
+fn activate(active: &mut Vec<ActiveChord>, held: Vec<Key>, mapping: &ChordMapping) -> ChordOutcome {
+    active.push(ActiveChord { held, up: Some((mapping.up.clone(), mapping.var_map.clone())) });
+    ChordOutcome::fire(mapping.down.clone(), mapping.var_map.clone())
+}
+
+impl ChordState {
+    pub fn new() -> Self {
+        ChordState { mappings: vec![], window: DEFAULT_CHORD_WINDOW, buffer: vec![], buffer_id: 0, active: vec![] }
+    }
+
+    pub fn add_mapping(&mut self, mapping: ChordMapping) {
+        self.mappings.retain(|v| !is_same_set(&v.keys, &mapping.keys));
+        self.mappings.push(mapping);
+    }
+
+    /// Removes the chord defined by the mapping, chords that are held down are still released.
+    pub fn remove_id(&mut self, id: usize) -> bool {
+        let len = self.mappings.len();
+        self.mappings.retain(|v| v.id != id);
+        self.mappings.len() != len
+    }
+
+    pub fn clear(&mut self) { self.mappings.clear(); }
+
+    fn buffered_keys(&self) -> Vec<Key> {
+        self.buffer.iter().map(|ev| Key { event_code: ev.event_code }).collect()
+    }
+
+    /// Starts buffering the event, returns the timer for the chord window if a new buffer was started.
+    fn push_to_buffer(&mut self, ev: InputEvent) -> Option<(usize, time::Duration)> {
+        self.buffer.push(ev);
+        if self.buffer.len() > 1 { return None; }
+
+        self.buffer_id += 1;
+        Some((self.buffer_id, self.window))
+    }
+
+    pub fn handle_input_ev(&mut self, ev: InputEvent) -> ChordOutcome {
+        let key = Key { event_code: ev.event_code };
+
+        // keys that belong to a chord which already fired are swallowed until released
+        if let Some(idx) = self.active.iter().position(|v| v.held.contains(&key)) {
+            if ev.value != TYPE_UP { return ChordOutcome::consumed(); }
+
+            let active = &mut self.active[idx];
+            active.held.retain(|v| *v != key);
+            // the chord is released as soon as the first key goes up
+            let up = active.up.take();
+            if active.held.is_empty() { self.active.remove(idx); }
+
+            return match up {
+                Some((block, var_map)) => ChordOutcome::fire(block, var_map),
+                None => ChordOutcome::consumed(),
+            };
+        }
+
+        if ev.value == TYPE_DOWN && self.mappings.iter().any(|v| v.keys.contains(&key)) {
+            let mut candidate = self.buffered_keys();
+            candidate.push(key);
+
+            // a longer chord that starts with the same keys can still be completed within the window
+            let is_prefix = self.mappings.iter().any(|v| v.keys.len() > candidate.len() && is_subset(&candidate, &v.keys));
+
+            if !is_prefix {
+                if let Some(mapping) = self.mappings.iter().find(|v| is_same_set(&v.keys, &candidate)) {
+                    self.buffer.clear();
+                    return activate(&mut self.active, candidate, mapping);
+                }
+            }
+
+            if self.mappings.iter().any(|v| is_subset(&candidate, &v.keys)) {
+                let timer = self.push_to_buffer(ev);
+                return ChordOutcome { fire: vec![], replay: vec![], timer };
+            }
+
+            // the chord was broken, the current key might still start a new one
+            let mut outcome = self.flush();
+            outcome.timer = self.push_to_buffer(ev);
+            return outcome;
+        }
+
+        if self.buffer.is_empty() { return ChordOutcome::replay(vec![ev]); }
+
+        // repeat events of buffered keys don't need to be replayed
+        if ev.value == TYPE_REPEAT && self.buffered_keys().contains(&key) { return ChordOutcome::consumed(); }
+
+        // the buffer might complete a shorter chord, the event then belongs to that chord
+        let mut outcome = self.flush();
+        outcome.append(self.handle_input_ev(ev));
+        outcome
+    }
+
+    /// Called once the chord window expires, fires the buffered chord if it is complete and
+    /// returns the buffered events otherwise.
+    pub fn handle_timeout(&mut self, id: usize) -> ChordOutcome {
+        if id != self.buffer_id { return ChordOutcome::consumed(); }
+        self.flush()
+    }
+
+    fn flush(&mut self) -> ChordOutcome {
+        let buffer = std::mem::take(&mut self.buffer);
+        let keys: Vec<Key> = buffer.iter().map(|ev| Key { event_code: ev.event_code }).collect();
+
+        match self.mappings.iter().find(|v| !keys.is_empty() && is_same_set(&v.keys, &keys)) {
+            Some(mapping) => activate(&mut self.active, keys, mapping),
+            None => ChordOutcome::replay(buffer),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tap::Tap;
+
+    use super::*;
+
+    fn state() -> ChordState {
+        let mut state = ChordState::new();
+        state.add_mapping(ChordMapping {
+            id: 0,
+            keys: vec![*KEY_J, *KEY_K],
+            down: Block::new().tap_mut(|b| { b.push_expr(Expr::KeyAction(KeyAction::new(*KEY_ESC, TYPE_DOWN))); }),
+            up: Block::new(),
+            var_map: Environment::new(),
+        });
+        state
+    }
+
+    fn ev(key: Key, value: i32) -> InputEvent { KeyAction::new(key, value).to_input_ev() }
+
+    #[test]
+    fn test_chord() {
+        let mut state = state();
+
+        let res = state.handle_input_ev(ev(*KEY_J, TYPE_DOWN));
+        assert_eq!(res.replay, vec![]);
+        assert!(res.fire.is_empty());
+        assert!(res.timer.is_some());
+
+        let res = state.handle_input_ev(ev(*KEY_K, TYPE_DOWN));
+        assert_eq!(res.replay, vec![]);
+        assert_eq!(res.fire.len(), 1);
+
+        let res = state.handle_input_ev(ev(*KEY_K, TYPE_UP));
+        assert_eq!(res.fire.len(), 1);
+        let res = state.handle_input_ev(ev(*KEY_J, TYPE_UP));
+        assert!(res.fire.is_empty());
+        assert_eq!(res.replay, vec![]);
+    }
+
+    #[test]
+    fn test_broken_chord() {
+        let mut state = state();
+
+        state.handle_input_ev(ev(*KEY_J, TYPE_DOWN));
+        let res = state.handle_input_ev(ev(*KEY_A, TYPE_DOWN));
+        assert_eq!(res.replay, vec![ev(*KEY_J, TYPE_DOWN), ev(*KEY_A, TYPE_DOWN)]);
+
+        state.handle_input_ev(ev(*KEY_J, TYPE_DOWN));
+        let res = state.handle_input_ev(ev(*KEY_J, TYPE_UP));
+        assert_eq!(res.replay, vec![ev(*KEY_J, TYPE_DOWN), ev(*KEY_J, TYPE_UP)]);
+    }
+
+    #[test]
+    fn test_chord_timeout() {
+        let mut state = state();
+
+        let (id, _) = state.handle_input_ev(ev(*KEY_J, TYPE_DOWN)).timer.unwrap();
+        assert_eq!(state.handle_timeout(id).replay, vec![ev(*KEY_J, TYPE_DOWN)]);
+        assert_eq!(state.handle_timeout(id).replay, vec![]);
+
+        let res = state.handle_input_ev(ev(*KEY_K, TYPE_DOWN));
+        assert_eq!(res.replay, vec![]);
+        assert!(res.fire.is_empty());
+    }
+
+    #[test]
+    fn test_longer_chord() {
+        let mut state = state();
+        state.add_mapping(ChordMapping {
+            id: 1,
+            keys: vec![*KEY_J, *KEY_K, *KEY_L],
+            down: Block::new(),
+            up: Block::new(),
+            var_map: Environment::new(),
+        });
+
+        // the shorter chord waits for the window since the longer one could still be completed
+        let (id, _) = state.handle_input_ev(ev(*KEY_J, TYPE_DOWN)).timer.unwrap();
+        assert!(state.handle_input_ev(ev(*KEY_K, TYPE_DOWN)).fire.is_empty());
+        let res = state.handle_input_ev(ev(*KEY_L, TYPE_DOWN));
+        assert_eq!(res.fire.len(), 1);
+        assert!(state.handle_timeout(id).fire.is_empty());
+        state.handle_input_ev(ev(*KEY_J, TYPE_UP));
+        state.handle_input_ev(ev(*KEY_K, TYPE_UP));
+        state.handle_input_ev(ev(*KEY_L, TYPE_UP));
+
+        // the shorter chord fires once the window expires
+        let (id, _) = state.handle_input_ev(ev(*KEY_J, TYPE_DOWN)).timer.unwrap();
+        assert!(state.handle_input_ev(ev(*KEY_K, TYPE_DOWN)).fire.is_empty());
+        let res = state.handle_timeout(id);
+        assert_eq!(res.fire.len(), 1);
+        assert_eq!(res.replay, vec![]);
+
+        // releasing a key within the window completes the shorter chord as well
+        state.handle_input_ev(ev(*KEY_J, TYPE_UP));
+        state.handle_input_ev(ev(*KEY_K, TYPE_UP));
+        state.handle_input_ev(ev(*KEY_J, TYPE_DOWN));
+        state.handle_input_ev(ev(*KEY_K, TYPE_DOWN));
+        let res = state.handle_input_ev(ev(*KEY_K, TYPE_UP));
+        assert_eq!(res.fire.len(), 2);
+        assert_eq!(res.replay, vec![]);
+    }
+}
@@ -4,20 +4,33 @@ use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, SubCommand};
 use xdg::BaseDirectories;
 
+use crate::runtime::error::ErrorPolicy;
+
 pub struct Configuration {
     pub script_file: fs::File,
+    pub script_path: String,
     pub verbosity: i32,
     pub devices: Vec<String>,
+    pub on_error: ErrorPolicy,
+}
+
+pub enum Command {
+    /// Grab the input devices and run the script.
+    Run(Configuration),
+    /// Statically check the script at the given path.
+    Check(String),
 }
 
-pub fn parse_cli() -> Result<Configuration> {
+pub fn parse_cli() -> Result<Command> {
     let matches = App::new("map2")
         .version("1.0")
         .author("shiro <shiro@usagi.io>")
         .about("A scripting language that allows complex key remapping on Linux.")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .setting(AppSettings::ArgsNegateSubcommands)
         .arg(Arg::with_name("verbosity")
             .short("-v")
             .long("--verbose")
@@ -29,12 +42,29 @@ pub fn parse_cli() -> Result<Configuration> {
             .long("--devices")
             .takes_value(true)
         )
+        .arg(Arg::with_name("on error")
+            .help("Exit on runtime errors or only log them and continue")
+            .long("--on-error")
+            .takes_value(true)
+            .possible_values(&["exit", "continue"])
+            .default_value("exit")
+        )
         .arg(Arg::with_name("script file")
             .help("Executes the given script file")
             .index(1)
             .required(true))
+        .subcommand(SubCommand::with_name("check")
+            .about("Checks a script for problems without running it")
+            .arg(Arg::with_name("script file")
+                .help("The script file to check")
+                .index(1)
+                .required(true)))
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("check") {
+        return Ok(Command::Check(matches.value_of("script file").unwrap().to_string()));
+    }
+
     let device_list_config_name = "devices.list";
 
     let xdg_dirs = BaseDirectories::with_prefix("map2")
@@ -69,11 +99,18 @@ pub fn parse_cli() -> Result<Configuration> {
 
     let verbosity = matches.occurrences_of("verbosity") as i32;
 
+    let on_error = match matches.value_of("on error") {
+        Some("continue") => ErrorPolicy::Continue,
+        _ => ErrorPolicy::Exit,
+    };
+
     let config = Configuration {
         script_file,
+        script_path,
         verbosity,
         devices: device_list,
+        on_error,
     };
 
-    Ok(config)
+    Ok(Command::Run(config))
 }
\ No newline at end of file
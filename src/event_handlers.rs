@@ -37,6 +37,132 @@ pub(crate) fn update_modifiers(state: &mut State, action: &KeyAction) {
     };
 }
 
+async fn write_key_actions(state: &mut State, actions: &[KeyAction], ev_writer: &mut mpsc::Sender<InputEvent>) {
+    for action in actions {
+        update_modifiers(state, action);
+        ev_writer.send(action.to_input_ev()).await.unwrap();
+        ev_writer.send(SYN_REPORT.clone()).await.unwrap();
+    }
+}
+
+/// Runs a block, errors are reported to the main loop.
+fn block_handler(block: Block, var_map: Environment, ev_writer: &mpsc::Sender<InputEvent>, message_tx: &ExecutionMessageSender,
+                 window_cycle_token: usize, modifier_state: Arc<KeyModifierState>) -> Handler {
+    let mut message_tx = message_tx.clone();
+    let ev_writer = ev_writer.clone();
+    Box::pin(async move {
+        let mut amb = Ambient { ev_writer_tx: ev_writer, message_tx: Some(&mut message_tx), window_cycle_token, modifier_state: &modifier_state, layer: None, window: None, rel_frame: false };
+
+        if let Err(err) = eval_block(&block, &var_map, &mut amb).await {
+            let _ = message_tx.send(ExecutionMessage::RuntimeError(err)).await;
+        }
+    })
+}
+
+/// Runs the first mapping target whose guard holds, the event is forwarded if none of them do.
+/// Repeat and release events go to the target that handled the press.
+fn mapping_handler(targets: Arc<Vec<MappingTarget>>, window: Option<ActiveWindowInfo>, ev: InputEvent, press: Option<usize>, pressed: PressedTargets,
+                   ev_writer: &mpsc::Sender<InputEvent>, message_tx: &ExecutionMessageSender, window_cycle_token: usize,
+                   modifier_state: Arc<KeyModifierState>) -> Handler {
+    let mut message_tx = message_tx.clone();
+    let ev_writer = ev_writer.clone();
+    Box::pin(async move {
+        let mut amb = Ambient { ev_writer_tx: ev_writer.clone(), message_tx: Some(&mut message_tx), window_cycle_token, modifier_state: &modifier_state, layer: None, window: None, rel_frame: false };
+        let action = KeyAction::from_input_ev(&ev);
+        let ret = match handling_target(&targets, window.as_ref(), &action, &pressed, &mut amb).await {
+            Ok(Some(target)) => {
+                // only the target that handles the press repeats it
+                if let (Some(press), Some(repeat)) = (press, target.repeat) {
+                    let _ = amb.message_tx.as_ref().unwrap().send(ExecutionMessage::StartKeyRepeat(press, repeat)).await;
+                }
+                eval_block(&target.block, &target.var_map, &mut amb).await.map(|_| true)
+            }
+            Ok(None) => Ok(false),
+            Err(err) => Err(err),
+        };
+        match ret {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(err) => {
+                let _ = message_tx.send(ExecutionMessage::RuntimeError(err)).await;
+                return;
+            }
+        }
+
+        if action.key.is_modifier() {
+            let _ = message_tx.send(ExecutionMessage::UpdateModifiers(action)).await;
+        }
+        let _ = ev_writer.send(ev).await;
+    })
+}
+
+/// Writes key actions once the handlers queued before it are done.
+fn key_actions_handler(actions: Vec<KeyAction>, ev_writer: &mpsc::Sender<InputEvent>, message_tx: &ExecutionMessageSender) -> Handler {
+    let message_tx = message_tx.clone();
+    let ev_writer = ev_writer.clone();
+    Box::pin(async move {
+        for action in actions {
+            if action.key.is_modifier() {
+                let _ = message_tx.send(ExecutionMessage::UpdateModifiers(action)).await;
+            }
+            let _ = ev_writer.send(action.to_input_ev()).await;
+            let _ = ev_writer.send(SYN_REPORT.clone()).await;
+        }
+    })
+}
+
+/// Runs the first mapping target of a mouse or wheel event whose guard holds, the event is forwarded
+/// if none of them do.
+fn rel_handler(targets: Arc<Vec<MappingTarget>>, window: Option<ActiveWindowInfo>, ev: InputEvent, ev_writer: &mpsc::Sender<InputEvent>, message_tx: &ExecutionMessageSender,
+               window_cycle_token: usize, modifier_state: Arc<KeyModifierState>) -> Handler {
+    let mut message_tx = message_tx.clone();
+    let ev_writer = ev_writer.clone();
+    Box::pin(async move {
+        let mut amb = Ambient { ev_writer_tx: ev_writer.clone(), message_tx: Some(&mut message_tx), window_cycle_token, modifier_state: &modifier_state, layer: None, window: None, rel_frame: true };
+        let res = match applying_target(&targets, window.as_ref(), &mut amb).await {
+            Ok(Some(target)) => eval_block(&target.block, &target.var_map, &mut amb).await.map(|_| ()),
+            Ok(None) => {
+                let _ = ev_writer.send(ev).await;
+                Ok(())
+            }
+            Err(err) => Err(err),
+        };
+        // the device's frame was already reported, what the handler wrote forms a frame of its own
+        let _ = ev_writer.send(SYN_REPORT.clone()).await;
+        if let Err(err) = res {
+            let _ = message_tx.send(ExecutionMessage::RuntimeError(err)).await;
+        }
+    })
+}
+
+/// Drops a high-resolution wheel event if the mapping of its wheel event applies, the mapping
+/// itself only runs for the wheel event.
+fn hi_res_handler(targets: Arc<Vec<MappingTarget>>, window: Option<ActiveWindowInfo>, ev: InputEvent, ev_writer: &mpsc::Sender<InputEvent>, message_tx: &ExecutionMessageSender,
+                  window_cycle_token: usize, modifier_state: Arc<KeyModifierState>) -> Handler {
+    let mut message_tx = message_tx.clone();
+    let ev_writer = ev_writer.clone();
+    Box::pin(async move {
+        let mut amb = Ambient { ev_writer_tx: ev_writer.clone(), message_tx: Some(&mut message_tx), window_cycle_token, modifier_state: &modifier_state, layer: None, window: None, rel_frame: false };
+        match applying_target(&targets, window.as_ref(), &mut amb).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                // the device's frame was already reported
+                let _ = ev_writer.send(ev).await;
+                let _ = ev_writer.send(SYN_REPORT.clone()).await;
+            }
+            Err(err) => { let _ = message_tx.send(ExecutionMessage::RuntimeError(err)).await; }
+        }
+    })
+}
+
+fn spawn_timeout(message_tx: &ExecutionMessageSender, duration: time::Duration, msg: ExecutionMessage) {
+    let message_tx = message_tx.clone();
+    task::spawn(async move {
+        tokio::time::sleep(duration).await;
+        let _ = message_tx.send(msg).await;
+    });
+}
+
 pub async fn handle_stdin_ev(
     mut state: &mut State,
     ev: InputEvent,
@@ -52,61 +178,309 @@ pub async fn handle_stdin_ev(
 
     match ev.event_code {
         EventCode::EV_KEY(_) => {}
+        EventCode::EV_REL(_) => {
+            handle_rel_ev(state, ev, ev_writer, message_tx, window_cycle_token).await;
+            return Ok(());
+        }
         _ => {
             ev_writer.send(ev).await.unwrap();
             return Ok(());
         }
     }
 
-    let mut from_modifiers = KeyModifierFlags::new();
-    from_modifiers.ctrl = state.modifiers.is_ctrl();
-    from_modifiers.alt = state.modifiers.is_alt();
-    from_modifiers.shift = state.modifiers.is_shift();
-    from_modifiers.meta = state.modifiers.is_meta();
+    // dual-role keys need to be resolved before anything else since they can change the modifier state
+    let tap_hold_outcome = state.tap_hold.handle_key_action(&KeyAction::from_input_ev(&ev), time::Instant::now());
+    if let Some((id, tapping_term)) = tap_hold_outcome.timer {
+        spawn_timeout(message_tx, tapping_term, ExecutionMessage::TapHoldTimeout(id));
+    }
+    write_key_actions(&mut state, &tap_hold_outcome.actions, ev_writer).await;
+    if tap_hold_outcome.consumed { return Ok(()); }
 
-    let from_key_action = KeyActionWithMods {
-        key: Key { event_code: ev.event_code },
-        value: ev.value,
-        modifiers: from_modifiers,
-    };
+    let chord_outcome = state.chords.handle_input_ev(ev);
+    handle_chord_outcome(&mut state, chord_outcome, mappings, ev_writer, message_tx, window_cycle_token).await
+}
 
-    if let Some(block) = mappings.0.get(&from_key_action) {
-        let block = block.clone();
-        let mut message_tx = message_tx.clone();
-        let ev_writer = ev_writer.clone();
-        let modifier_state = state.modifiers.clone();
-        task::spawn(async move {
-            let (block, var_map) = block.deref();
-            let mut amb = Ambient { ev_writer_tx: ev_writer, message_tx: Some(&mut message_tx), window_cycle_token, modifier_state: &modifier_state };
+async fn handle_chord_outcome(
+    state: &mut State,
+    outcome: ChordOutcome,
+    mappings: &mut CompiledKeyMappings,
+    ev_writer: &mut mpsc::Sender<InputEvent>,
+    message_tx: &mut ExecutionMessageSender,
+    window_cycle_token: usize,
+) -> Result<()> {
+    if let Some((id, window)) = outcome.timer {
+        spawn_timeout(message_tx, window, ExecutionMessage::ChordTimeout(id));
+    }
+    for (block, var_map) in outcome.fire {
+        let handler = block_handler(block, var_map, ev_writer, message_tx, window_cycle_token, state.modifiers.clone());
+        state.handler_queues.push(None, handler);
+    }
+    for ev in outcome.replay {
+        handle_key_ev(state, ev, mappings, ev_writer, message_tx, window_cycle_token).await?;
+    }
+    Ok(())
+}
 
-            eval_block(&block, &var_map, &mut amb).await;
-        });
-        return Ok(());
+/// Runs a key event through the sequence trigger matcher.
+async fn handle_key_ev(
+    state: &mut State,
+    ev: InputEvent,
+    mappings: &mut CompiledKeyMappings,
+    ev_writer: &mut mpsc::Sender<InputEvent>,
+    message_tx: &mut ExecutionMessageSender,
+    window_cycle_token: usize,
+) -> Result<()> {
+    let modifiers = state.modifiers.clone();
+    let outcome = state.sequences.handle_input_ev(ev, &modifiers);
+    handle_sequence_outcome(state, outcome, mappings, ev_writer, message_tx, window_cycle_token).await
+}
+
+async fn handle_sequence_outcome(
+    state: &mut State,
+    outcome: SequenceOutcome,
+    mappings: &mut CompiledKeyMappings,
+    ev_writer: &mut mpsc::Sender<InputEvent>,
+    message_tx: &mut ExecutionMessageSender,
+    window_cycle_token: usize,
+) -> Result<()> {
+    if let Some((id, timeout)) = outcome.timer {
+        spawn_timeout(message_tx, timeout, ExecutionMessage::SequenceTimeout(id));
+    }
+    if let Some((block, var_map)) = outcome.fire {
+        let handler = block_handler(block, var_map, ev_writer, message_tx, window_cycle_token, state.modifiers.clone());
+        state.handler_queues.push(None, handler);
     }
+    for ev in outcome.replay {
+        handle_mapped_key_ev(state, ev, mappings, ev_writer, message_tx, window_cycle_token).await?;
+    }
+    Ok(())
+}
 
-    update_modifiers(&mut state, &KeyAction::from_input_ev(&ev));
+/// Looks up the mapping for a relative event of a mouse or wheel, events without a mapping are
+/// forwarded as-is.
+async fn handle_rel_ev(
+    state: &mut State,
+    ev: InputEvent,
+    ev_writer: &mut mpsc::Sender<InputEvent>,
+    message_tx: &mut ExecutionMessageSender,
+    window_cycle_token: usize,
+) {
+    if let Some(lookup) = state.rel_mappings.lookup(&ev) {
+        if lookup.targets.iter().any(|v| v.applies_to(state.active_window.as_ref())) {
+            let handler = if lookup.hi_res { hi_res_handler } else { rel_handler };
+            let handler = handler(lookup.targets, state.active_window.clone(), ev, ev_writer, message_tx, window_cycle_token, state.modifiers.clone());
+            state.handler_queues.push(Some(lookup.axis), handler);
+            return;
+        }
+    }
 
     ev_writer.send(ev).await.unwrap();
+}
+
+/// Runs a key event through the sticky modifier tracker before looking up its mapping.
+async fn handle_mapped_key_ev(
+    state: &mut State,
+    ev: InputEvent,
+    mappings: &mut CompiledKeyMappings,
+    ev_writer: &mut mpsc::Sender<InputEvent>,
+    message_tx: &mut ExecutionMessageSender,
+    window_cycle_token: usize,
+) -> Result<()> {
+    // software repeat replaces the kernel's repeat, pressing or releasing a key stops it
+    let action = KeyAction::from_input_ev(&ev);
+    if action.value == TYPE_REPEAT && state.key_repeat.is_repeating(action.key) { return Ok(()); }
+    if action.value == TYPE_DOWN { state.key_repeat.press(action.key); }
+    if action.value == TYPE_UP { state.key_repeat.release(action.key); }
+
+    let sticky_outcome = state.sticky_modifiers.handle_key_action(&action);
+    if sticky_outcome.consumed { return Ok(()); }
+
+    let queued = lookup_key_ev(state, ev, mappings, ev_writer, message_tx, window_cycle_token).await?;
+
+    // one-shot modifiers are released once the key they applied to was released, which needs to
+    // wait for the mapping of that key
+    if queued && !sticky_outcome.after.is_empty() {
+        let handler = key_actions_handler(sticky_outcome.after, ev_writer, message_tx);
+        state.handler_queues.push(Some(action.key), handler);
+    } else {
+        write_key_actions(state, &sticky_outcome.after, ev_writer).await;
+    }
 
     Ok(())
 }
 
+/// Looks up the mapping for a key event in the active layers, events without a mapping are forwarded as-is.
+/// Returns whether the event was queued for a mapping handler.
+async fn lookup_key_ev(
+    mut state: &mut State,
+    ev: InputEvent,
+    mappings: &mut CompiledKeyMappings,
+    ev_writer: &mut mpsc::Sender<InputEvent>,
+    message_tx: &mut ExecutionMessageSender,
+    window_cycle_token: usize,
+) -> Result<bool> {
+    if state.layers.handle_momentary(&KeyAction::from_input_ev(&ev)) { return Ok(false); }
+
+    // side-specific mappings take precedence over generic ones, wildcard mappings come last
+    let key = Key { event_code: ev.event_code };
+    let candidates: Vec<KeyActionWithMods> = state.modifiers.matching_flags().into_iter()
+        .chain(state.modifiers.wildcard_flags())
+        .map(|modifiers| KeyActionWithMods::new(key, ev.value, modifiers))
+        .collect();
+
+    // the release of a key whose mapping was removed while it was held still goes to that mapping
+    let targets = match state.removed_targets.lookup(&candidates) {
+        Some(targets) => Some(targets),
+        None => state.layers.lookup(&candidates, mappings),
+    };
+    if let Some(targets) = targets {
+        // the handler starts the software repeat of a press once it knows which target handles it
+        let press = if ev.value == TYPE_DOWN { state.key_repeat.pressed(key) } else { None };
+
+        // handlers of the same key run in event order, the handler also checks the window so that
+        // the release of a key goes to the same place as its press when the focus changed in between
+        let handler = mapping_handler(targets, state.active_window.clone(), ev, press, state.pressed_targets.clone(), ev_writer,
+                                      message_tx, window_cycle_token, state.modifiers.clone());
+        state.handler_queues.push(Some(key), handler);
+        return Ok(true);
+    }
+
+    update_modifiers(&mut state, &KeyAction::from_input_ev(&ev));
+
+    ev_writer.send(ev).await.unwrap();
+
+    Ok(false)
+}
+
 
+/// Returns the mappings of the given layer, or the base mappings if no layer is given.
+fn scoped_mappings<'a>(state: &'a mut State, mappings: &'a mut CompiledKeyMappings, layer: Option<String>) -> &'a mut CompiledKeyMappings {
+    match layer {
+        Some(layer) => state.layers.layers.entry(layer).or_insert_with(CompiledKeyMappings::new),
+        None => mappings,
+    }
+}
+
+/// Returns the exit code if the script should terminate.
 pub async fn handle_execution_message(
     out: &mut impl Write,
+    err_out: &mut impl Write,
     current_token: usize,
     msg: ExecutionMessage,
     state: &mut State,
     mappings: &mut CompiledKeyMappings,
-    window_change_handlers: &mut Vec<(Block, GuardedVarMap)>,
-) {
+    window_change_handlers: &mut Vec<(Block, Environment)>,
+    ev_writer: &mut mpsc::Sender<InputEvent>,
+    message_tx: &mut ExecutionMessageSender,
+    configuration: &Configuration,
+) -> Option<i32> {
     match msg {
         // ExecutionMessage::EatEv(action) => {
         //     state.ignore_list.ignore(&action);
         // }
-        ExecutionMessage::AddMapping(token, from, to, var_map) => {
+        ExecutionMessage::AddMapping(token, layer, from, target) => {
+            if token == current_token {
+                match layer {
+                    Some(layer) => state.layers.add_mapping(layer, from, target),
+                    None => mappings.insert(from, target),
+                }
+            }
+        }
+        ExecutionMessage::RemoveMapping(layer, triggers) => {
+            let mappings = scoped_mappings(state, mappings, layer);
+            let removed = CompiledKeyMappings(triggers.into_iter()
+                .filter_map(|from| mappings.0.remove_entry(&from))
+                .collect());
+            state.removed_targets.keep(removed, &state.pressed_targets);
+        }
+        ExecutionMessage::RemoveMappingById(id) => {
+            let removed = mappings.take_id(id);
+            state.removed_targets.keep(removed, &state.pressed_targets);
+            state.rel_mappings.remove_id(id);
+            state.tap_hold.remove_id(id);
+            state.chords.remove_id(id);
+            for layer in state.layers.layers.values_mut() {
+                state.removed_targets.keep(layer.take_id(id), &state.pressed_targets);
+            }
+            for ev in state.sequences.remove_id(id) {
+                handle_mapped_key_ev(state, ev, mappings, ev_writer, message_tx, current_token).await.unwrap();
+            }
+        }
+        ExecutionMessage::RemoveAllMappings(layer) => {
+            // mouse, dual-role, chord and sequence mappings don't belong to a layer
+            if layer.is_none() {
+                state.rel_mappings.clear();
+                state.tap_hold.clear();
+                state.chords.clear();
+                for ev in state.sequences.clear() {
+                    handle_mapped_key_ev(state, ev, mappings, ev_writer, message_tx, current_token).await.unwrap();
+                }
+            }
+            let removed = std::mem::replace(scoped_mappings(state, mappings, layer), CompiledKeyMappings::new());
+            state.removed_targets.keep(removed, &state.pressed_targets);
+        }
+        ExecutionMessage::GetMappings(layer, tx) => {
+            let _ = tx.send(scoped_mappings(state, mappings, layer).triggers()).await;
+        }
+        ExecutionMessage::AddTapHoldMapping(token, id, key, tap_hold) => {
+            if token == current_token {
+                state.tap_hold.add_mapping(id, key, tap_hold);
+            }
+        }
+        ExecutionMessage::TapHoldTimeout(id) => {
+            let actions = state.tap_hold.handle_timeout(id);
+            write_key_actions(state, &actions, ev_writer).await;
+        }
+        ExecutionMessage::AddChordMapping(token, mapping) => {
+            if token == current_token {
+                state.chords.add_mapping(mapping);
+            }
+        }
+        ExecutionMessage::SetChordWindow(window) => {
+            state.chords.window = window;
+        }
+        ExecutionMessage::ChordTimeout(id) => {
+            let outcome = state.chords.handle_timeout(id);
+            handle_chord_outcome(state, outcome, mappings, ev_writer, message_tx, current_token).await.unwrap();
+        }
+        ExecutionMessage::AddSequenceMapping(token, mapping) => {
+            if token == current_token {
+                state.sequences.add_mapping(mapping);
+            }
+        }
+        ExecutionMessage::AddRelMapping(token, trigger, target) => {
+            if token == current_token {
+                state.rel_mappings.insert(trigger, target);
+            }
+        }
+        ExecutionMessage::SetLeader(leader) => {
+            state.sequences.leader = leader;
+        }
+        ExecutionMessage::SetHandlerOrder(order) => {
+            state.handler_queues.order = order;
+        }
+        ExecutionMessage::SetSequenceTimeout(timeout) => {
+            state.sequences.timeout = timeout;
+        }
+        ExecutionMessage::SequenceTimeout(id) => {
+            let outcome = state.sequences.handle_timeout(id);
+            handle_sequence_outcome(state, outcome, mappings, ev_writer, message_tx, current_token).await.unwrap();
+        }
+        ExecutionMessage::AddMomentaryLayer(token, key, layer) => {
             if token == current_token {
-                mappings.0.insert(from, Arc::new((to, var_map)));
+                state.layers.momentary.insert(key, layer);
+            }
+        }
+        ExecutionMessage::LayerOn(layer) => { state.layers.layer_on(layer); }
+        ExecutionMessage::LayerOff(layer) => { state.layers.layer_off(&layer); }
+        ExecutionMessage::LayerToggle(layer) => { state.layers.layer_toggle(layer); }
+        ExecutionMessage::LayerOneShot(layer) => { state.layers.layer_one_shot(layer); }
+        ExecutionMessage::SetStickyModifiers(enabled, lock_on_double_tap) => {
+            state.sticky_modifiers.enabled = enabled;
+            state.sticky_modifiers.lock_on_double_tap = lock_on_double_tap;
+            if !enabled {
+                let actions = state.sticky_modifiers.release_all();
+                write_key_actions(state, &actions, ev_writer).await;
             }
         }
         ExecutionMessage::GetFocusedWindowInfo(tx) => {
@@ -115,39 +489,75 @@ pub async fn handle_execution_message(
         ExecutionMessage::RegisterWindowChangeCallback(block, var_map) => {
             window_change_handlers.push((block, var_map));
         }
+        ExecutionMessage::AddTimer(id, timer) => {
+            spawn_timeout(message_tx, timer.delay, ExecutionMessage::TimerFired(id));
+            state.timers.add(id, timer);
+        }
+        ExecutionMessage::ClearTimer(id) => { state.timers.clear(id); }
+        ExecutionMessage::TimerFired(id) => {
+            if let Some(timer) = state.timers.fire(id) {
+                if timer.repeat {
+                    spawn_timeout(message_tx, timer.delay, ExecutionMessage::TimerFired(id));
+                }
+                // timers don't wait for mapping handlers
+                task::spawn(block_handler(timer.block, timer.var_map, ev_writer, message_tx, current_token, state.modifiers.clone()));
+            }
+        }
+        ExecutionMessage::StartKeyRepeat(press, repeat) => {
+            if let Some((id, delay)) = state.key_repeat.start(press, repeat) {
+                spawn_timeout(message_tx, delay, ExecutionMessage::KeyRepeat(id));
+            }
+        }
+        ExecutionMessage::KeyRepeat(id) => {
+            if let Some((key, interval)) = state.key_repeat.tick(id) {
+                spawn_timeout(message_tx, interval, ExecutionMessage::KeyRepeat(id));
+                // repeats are queued like the key's other events, so they can't run after its release
+                let ev = KeyAction::new(key, TYPE_REPEAT).to_input_ev();
+                if let Err(err) = lookup_key_ev(state, ev, mappings, ev_writer, message_tx, current_token).await {
+                    writeln!(err_out, "error: {}", err).unwrap();
+                }
+            }
+        }
         ExecutionMessage::Write(message) => {
             out.write(message.as_ref()).unwrap();
         }
         ExecutionMessage::UpdateModifiers(action) => {
             event_handlers::update_modifiers(state, &action);
         }
-        ExecutionMessage::Exit(exit_code) => { std::process::exit(exit_code) }
-        ExecutionMessage::FatalError(err, exit_code) => {
-            eprintln!("error: {}", err);
-            std::process::exit(exit_code)
+        ExecutionMessage::Exit(exit_code) => { return Some(exit_code); }
+        ExecutionMessage::RuntimeError(err) => {
+            writeln!(err_out, "error: {}", err).unwrap();
+            if configuration.on_error == ErrorPolicy::Exit { return Some(1); }
         }
     }
+    None
 }
 
 
 pub fn handle_active_window_change(ev_writer_tx: &mut mpsc::Sender<InputEvent>, message_tx: &mut ExecutionMessageSender,
-                                   window_cycle_token: usize, window_change_handlers: &mut Vec<(Block, GuardedVarMap)>) {
+                                   window_cycle_token: usize, window_change_handlers: &mut Vec<(Block, Environment)>) {
     for (handler, var_map) in window_change_handlers {
         let mut message_tx = message_tx.clone();
         let ev_writer_tx = ev_writer_tx.clone();
         let handler = handler.clone();
-        let mut var_map = var_map.clone();
+        let var_map = var_map.clone();
 
         task::spawn(async move {
-            eval_block(&handler,
-                       &mut var_map,
-                       &mut Ambient {
-                           ev_writer_tx,
-                           message_tx: Some(&mut message_tx),
-                           window_cycle_token,
-                           modifier_state: &KeyModifierState::new(),
-                       },
+            let ret = eval_block(&handler,
+                                 &var_map,
+                                 &mut Ambient {
+                                     ev_writer_tx,
+                                     message_tx: Some(&mut message_tx),
+                                     window_cycle_token,
+                                     modifier_state: &KeyModifierState::new(),
+                                     layer: None,
+                                     window: None,
+                                     rel_frame: false,
+                                 },
             ).await;
+            if let Err(err) = ret {
+                let _ = message_tx.send(ExecutionMessage::RuntimeError(err)).await;
+            }
         });
     }
 }
\ No newline at end of file
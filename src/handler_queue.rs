@@ -0,0 +1,89 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::*;
+
+/// Which mapping handlers wait for each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HandlerOrder {
+    /// Handlers of the same key run one after another in event order, handlers of different keys
+    /// run concurrently.
+    PerKey,
+    /// All handlers run one after another in event order.
+    Global,
+}
+
+pub type Handler = Pin<Box<dyn Future<Output=()> + Send>>;
+
+/// Runs mapping handlers in the order their events arrived, each queue has a task that runs its
+/// handlers one at a time.
+pub struct HandlerQueues {
+    pub order: HandlerOrder,
+    queues: HashMap<Option<Key>, mpsc::UnboundedSender<Handler>>,
+}
+
+impl HandlerQueues {
+    pub fn new() -> Self { HandlerQueues { order: HandlerOrder::PerKey, queues: HashMap::new() } }
+
+    /// Queues a handler behind the earlier handlers of the same key, handlers that aren't triggered
+    /// by a single key, e.g. chords and sequences, share a queue.
+    pub fn push(&mut self, key: Option<Key>, handler: Handler) {
+        let key = match self.order {
+            HandlerOrder::PerKey => key,
+            HandlerOrder::Global => None,
+        };
+
+        let queue = self.queues.entry(key).or_insert_with(|| {
+            let (tx, mut rx) = mpsc::unbounded_channel::<Handler>();
+            task::spawn(async move {
+                while let Some(handler) = rx.recv().await {
+                    // handlers run in their own task so a panicking handler doesn't stop the queue
+                    let _ = task::spawn(handler).await;
+                }
+            });
+            tx
+        });
+
+        let _ = queue.send(handler);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler(log: &Arc<Mutex<Vec<&'static str>>>, name: &'static str, delay: u64) -> Handler {
+        let log = log.clone();
+        Box::pin(async move {
+            tokio::time::sleep(time::Duration::from_millis(delay)).await;
+            log.lock().unwrap().push(name);
+        })
+    }
+
+    #[tokio::test]
+    async fn test_per_key() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut queues = HandlerQueues::new();
+
+        queues.push(Some(*KEY_A), handler(&log, "a down", 50));
+        queues.push(Some(*KEY_A), handler(&log, "a up", 0));
+        queues.push(Some(*KEY_B), handler(&log, "b down", 0));
+        tokio::time::sleep(time::Duration::from_millis(100)).await;
+
+        assert_eq!(*log.lock().unwrap(), vec!["b down", "a down", "a up"]);
+    }
+
+    #[tokio::test]
+    async fn test_global() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut queues = HandlerQueues::new();
+        queues.order = HandlerOrder::Global;
+
+        queues.push(Some(*KEY_A), handler(&log, "a down", 50));
+        queues.push(Some(*KEY_B), handler(&log, "b down", 0));
+        tokio::time::sleep(time::Duration::from_millis(100)).await;
+
+        assert_eq!(*log.lock().unwrap(), vec!["a down", "b down"]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+
+use xdg::BaseDirectories;
+
+use crate::*;
+
+/// Replaces the imports of a script with the statements of the imported files. Files are looked up
+/// relative to the importing file first and in the map2 config directory second.
+///
+/// A file is only evaluated the first time it's imported, so its mappings aren't registered twice
+/// if several files import it. Later imports with a namespace refer to the module of the first
+/// import.
+pub fn resolve_imports(block: Block, file: &Path) -> Result<Block> {
+    let mut imports = Imports {
+        stack: vec![(canonical(file), file.display().to_string())],
+        imported: HashMap::new(),
+    };
+    resolve_block(block, file, None, &mut imports)
+}
+
+struct Imports {
+    /// the files that are currently being imported, used for detecting cycles
+    stack: Vec<(PathBuf, String)>,
+    /// the files that were imported already, along with the qualified namespace of their module
+    /// or `None` if they were imported without a namespace
+    imported: HashMap<PathBuf, Option<String>>,
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn find_import(path: &str, importing_file: &Path) -> Option<PathBuf> {
+    let relative = importing_file.parent().unwrap_or_else(|| Path::new("")).join(path);
+    if relative.is_file() { return Some(relative); }
+
+    BaseDirectories::with_prefix("map2").ok()
+        .and_then(|dirs| dirs.find_config_file(path))
+}
+
+/// `namespace` is the qualified namespace of the module the block belongs to, `None` at the top
+/// level of the script.
+fn resolve_block(block: Block, file: &Path, namespace: Option<&str>, imports: &mut Imports) -> Result<Block> {
+    let mut resolved = Block::new();
+
+    for (idx, stmt) in block.statements.iter().enumerate() {
+        let span = block.span(idx);
+
+        let (path, import_namespace) = match stmt {
+            Stmt::Import(path, namespace) => (path, namespace),
+            _ => {
+                resolved.statements.push(stmt.clone());
+                resolved.spans.push(span);
+                continue;
+            }
+        };
+
+        let import_path = find_import(path, file)
+            .ok_or_else(|| anyhow!("{}: imported file '{}' not found next to '{}' or in the map2 config directory",
+                                   span, path, file.display()))?;
+        let import_name = import_path.display().to_string();
+
+        let canonical_path = canonical(&import_path);
+        if imports.stack.iter().any(|(v, _)| *v == canonical_path) {
+            let chain: Vec<&str> = imports.stack.iter().map(|(_, name)| name.as_str()).collect();
+            return Err(anyhow!("{}: import cycle: {} -> {}", span, chain.join(" -> "), import_name));
+        }
+
+        if let Some(module) = imports.imported.get(&canonical_path) {
+            match (import_namespace, module) {
+                (None, None) => {}
+                (None, Some(module)) => {
+                    return Err(anyhow!("{}: '{}' was already imported as '{}'", span, import_name, module));
+                }
+                (Some(import_namespace), Some(module)) => {
+                    resolved.statements.push(Stmt::Expr(Expr::Init(import_namespace.clone(),
+                                                                    Box::new(Expr::Name(module.clone())))));
+                    resolved.spans.push(span);
+                }
+                (Some(_), None) => {
+                    return Err(anyhow!("{}: '{}' was already imported without a namespace", span, import_name));
+                }
+            }
+            continue;
+        }
+
+        let raw = fs::read_to_string(&import_path)
+            .map_err(|err| anyhow!("{}: failed to read imported file '{}': {}", span, import_name, err))?;
+        let imported = parsing::parser::parse_script(&raw, &import_name)
+            .map_err(|err| anyhow!("{}: failed to parse imported file '{}': {}", span, import_name, err))?;
+
+        let module = import_namespace.as_ref().map(|import_namespace| match namespace {
+            Some(namespace) => format!("{}.{}", namespace, import_namespace),
+            None => import_namespace.clone(),
+        });
+        imports.imported.insert(canonical_path.clone(), module.clone());
+
+        imports.stack.push((canonical_path, import_name));
+        let imported = resolve_block(imported, &import_path, module.as_deref().or(namespace), imports)?;
+        imports.stack.pop();
+
+        match import_namespace {
+            Some(import_namespace) => {
+                resolved.statements.push(Stmt::Namespace(import_namespace.clone(), imported));
+                resolved.spans.push(span);
+            }
+            None => {
+                resolved.statements.extend(imported.statements);
+                resolved.spans.extend(imported.spans);
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tap::Tap;
+
+    use super::*;
+
+    fn resolve(file: &str) -> Result<Block> {
+        let raw = fs::read_to_string(file)?;
+        let block = parsing::parser::parse_script(&raw, file)?;
+        resolve_imports(block, Path::new(file))
+    }
+
+    #[test]
+    fn test_resolve() {
+        let block = resolve("examples/imports.m2").unwrap();
+        assert!(block.statements.iter().all(|stmt| !matches!(stmt, Stmt::Import(..))));
+        assert!(block.statements.iter().any(|stmt| matches!(stmt, Stmt::Namespace(name, _) if name == "common")));
+
+        // imported statements keep the location in their own file
+        assert!(block.spans.iter().any(|span| span.file.as_deref() == Some("examples/lib/mappings.m2")));
+    }
+
+    #[test]
+    fn test_errors() {
+        let dir = std::env::temp_dir().join(format!("map2-imports-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.m2"), "import \"b.m2\";").unwrap();
+        fs::write(dir.join("b.m2"), "let x = 1;\nimport \"a.m2\";").unwrap();
+        fs::write(dir.join("c.m2"), "import \"missing.m2\";").unwrap();
+
+        let a = dir.join("a.m2").display().to_string();
+        let b = dir.join("b.m2").display().to_string();
+        let err = resolve(&a).unwrap_err().to_string();
+        assert_eq!(err, format!("{}:2:1: import cycle: {} -> {} -> {}", b, a, b, a));
+
+        let c = dir.join("c.m2").display().to_string();
+        let err = resolve(&c).unwrap_err().to_string();
+        assert!(err.starts_with(&format!("{}:1:1: imported file 'missing.m2' not found", c)), "{}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_repeated_imports() {
+        let dir = std::env::temp_dir().join(format!("map2-repeated-imports-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("shared.m2"), "let x = 1;").unwrap();
+        fs::write(dir.join("left.m2"), "import \"shared.m2\" as shared;").unwrap();
+        fs::write(dir.join("right.m2"), "import \"shared.m2\" as other;").unwrap();
+        fs::write(dir.join("main.m2"), "import \"left.m2\";\nimport \"right.m2\";\nimport \"left.m2\";").unwrap();
+        fs::write(dir.join("flat.m2"), "import \"shared.m2\";\nimport \"shared.m2\" as shared;").unwrap();
+        fs::write(dir.join("namespaced.m2"), "import \"shared.m2\" as shared;\nimport \"shared.m2\";").unwrap();
+
+        // every file is only evaluated once, later namespaces refer to the first module
+        let block = resolve(&dir.join("main.m2").display().to_string()).unwrap();
+        assert_eq!(block.statements, vec![
+            Stmt::Namespace("shared".to_string(), Block::new().tap_mut(|b| {
+                b.push_expr(Expr::Init("x".to_string(), Box::new(Expr::Value(ValueType::Number(1.0)))));
+            })),
+            Stmt::Expr(Expr::Init("other".to_string(), Box::new(Expr::Name("shared".to_string())))),
+        ]);
+
+        let flat = dir.join("flat.m2").display().to_string();
+        let err = resolve(&flat).unwrap_err().to_string();
+        assert!(err.starts_with(&format!("{}:2:1: ", flat)), "{}", err);
+        assert!(err.ends_with("was already imported without a namespace"), "{}", err);
+
+        let namespaced = dir.join("namespaced.m2").display().to_string();
+        let err = resolve(&namespaced).unwrap_err().to_string();
+        assert!(err.starts_with(&format!("{}:2:1: ", namespaced)), "{}", err);
+        assert!(err.ends_with("was already imported as 'shared'"), "{}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -22,12 +22,15 @@ pub static ref KEY_ESC: Key = Key::from_str(&EventType::EV_KEY, "KEY_ESC").unwra
 pub static ref KEY_TAB: Key = Key::from_str(&EventType::EV_KEY, "KEY_TAB").unwrap();
 pub static ref KEY_SPACE: Key = Key::from_str(&EventType::EV_KEY, "KEY_SPACE").unwrap();
 pub static ref KEY_MINUS: Key = Key::from_str(&EventType::EV_KEY, "KEY_MINUS").unwrap();
+pub static ref KEY_BACKSLASH: Key = Key::from_str(&EventType::EV_KEY, "KEY_BACKSLASH").unwrap();
 pub static ref KEY_SLASH: Key = Key::from_str(&EventType::EV_KEY, "KEY_SLASH").unwrap();
 pub static ref KEY_CAPSLOCK: Key = Key::from_str(&EventType::EV_KEY, "KEY_CAPSLOCK").unwrap();
 pub static ref KEY_LEFT: Key = Key::from_str(&EventType::EV_KEY, "KEY_LEFT").unwrap();
 pub static ref KEY_RIGHT: Key = Key::from_str(&EventType::EV_KEY, "KEY_RIGHT").unwrap();
 pub static ref KEY_UP: Key = Key::from_str(&EventType::EV_KEY, "KEY_UP").unwrap();
 pub static ref KEY_DOWN: Key = Key::from_str(&EventType::EV_KEY, "KEY_DOWN").unwrap();
+pub static ref KEY_HOME: Key = Key::from_str(&EventType::EV_KEY, "KEY_HOME").unwrap();
+pub static ref KEY_F3: Key = Key::from_str(&EventType::EV_KEY, "KEY_F3").unwrap();
 pub static ref KEY_F4: Key = Key::from_str(&EventType::EV_KEY, "KEY_F4").unwrap();
 pub static ref KEY_F5: Key = Key::from_str(&EventType::EV_KEY, "KEY_F5").unwrap();
 pub static ref KEY_A: Key = Key::from_str(&EventType::EV_KEY, "KEY_A").unwrap();
@@ -71,6 +74,9 @@ lazy_static! {
     pub(crate) static ref KEY_ALIAS_TABLE: HashMap<&'static str, (Key, KeyModifierFlags)> = {
         let mut m = HashMap::new();
         m.insert(" ", (Key::from_str(&EventType::EV_KEY, "KEY_SPACE").unwrap(), KeyModifierFlags::new()));
+        m.insert("\n", (Key::from_str(&EventType::EV_KEY, "KEY_ENTER").unwrap(), KeyModifierFlags::new()));
+        m.insert("\r", (Key::from_str(&EventType::EV_KEY, "KEY_ENTER").unwrap(), KeyModifierFlags::new()));
+        m.insert("\t", (Key::from_str(&EventType::EV_KEY, "KEY_TAB").unwrap(), KeyModifierFlags::new()));
         m.insert("#", (Key::from_str(&EventType::EV_KEY, "KEY_3").unwrap(), KeyModifierFlags::new().tap_mut(|f|{f.shift();})));
         m.insert("$", (Key::from_str(&EventType::EV_KEY, "KEY_4").unwrap(), KeyModifierFlags::new().tap_mut(|f|{f.shift();})));
         m.insert("%", (Key::from_str(&EventType::EV_KEY, "KEY_5").unwrap(), KeyModifierFlags::new().tap_mut(|f|{f.shift();})));
@@ -91,8 +97,8 @@ lazy_static! {
         m.insert("?", (Key::from_str(&EventType::EV_KEY, "KEY_SLASH").unwrap(), KeyModifierFlags::new()));
         m.insert("@", (Key::from_str(&EventType::EV_KEY, "KEY_1").unwrap(), KeyModifierFlags::new().tap_mut(|f|{f.shift();})));
         m.insert("[", (Key::from_str(&EventType::EV_KEY, "KEY_LEFTBRACE").unwrap(), KeyModifierFlags::new()));
-        // TODO implement escaped chars so this doesn't break sequence parsing
-        // m.insert("\"", (Key::from_str(&EventType::EV_KEY, "KEY_APOSTROPHE").unwrap(), KeyModifierFlags::new()));
+        m.insert("\\", (Key::from_str(&EventType::EV_KEY, "KEY_BACKSLASH").unwrap(), KeyModifierFlags::new()));
+        m.insert("\"", (Key::from_str(&EventType::EV_KEY, "KEY_APOSTROPHE").unwrap(), KeyModifierFlags::new().tap_mut(|f|{f.shift();})));
         m.insert("]", (Key::from_str(&EventType::EV_KEY, "KEY_RIGHTBRACE").unwrap(), KeyModifierFlags::new()));
         m.insert("^", (Key::from_str(&EventType::EV_KEY, "KEY_6").unwrap(), KeyModifierFlags::new().tap_mut(|f|{f.shift();})));
         m.insert("{", (Key::from_str(&EventType::EV_KEY, "KEY_LEFTBRACE").unwrap(), KeyModifierFlags::new().tap_mut(|f|{f.shift();})));
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use evdev_rs::enums::{EventCode, EventType};
 
 use crate::*;
@@ -14,6 +16,19 @@ impl Key {
             None => { Err(anyhow!("key not found: '{}'", s)) }
         }
     }
+
+    pub fn is_modifier(&self) -> bool {
+        [*KEY_LEFT_CTRL, *KEY_RIGHT_CTRL, *KEY_LEFT_ALT, *KEY_RIGHT_ALT, *KEY_LEFT_SHIFT, *KEY_RIGHT_SHIFT, *KEY_LEFT_META, *KEY_RIGHT_META]
+            .contains(self)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // keys are written the way they are used in scripts (i.e. 'KEY_ENTER' => 'enter')
+        let name = self.event_code.to_string();
+        write!(f, "{}", name.strip_prefix("KEY_").unwrap_or(&name).to_lowercase())
+    }
 }
 
 
@@ -32,16 +47,59 @@ impl KeyValue {
     }
 }
 
+/// Restricts a modifier flag to one side of the keyboard.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ModifierSide { Any, Left, Right }
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Modifier { Ctrl, Shift, Alt, Meta }
+
+impl Modifier {
+    pub const ALL: [Modifier; 4] = [Modifier::Ctrl, Modifier::Shift, Modifier::Alt, Modifier::Meta];
+
+    pub fn key(&self, side: ModifierSide) -> Key {
+        match (self, side) {
+            (Modifier::Ctrl, ModifierSide::Right) => *KEY_RIGHT_CTRL,
+            (Modifier::Ctrl, _) => *KEY_LEFT_CTRL,
+            (Modifier::Shift, ModifierSide::Right) => *KEY_RIGHT_SHIFT,
+            (Modifier::Shift, _) => *KEY_LEFT_SHIFT,
+            (Modifier::Alt, ModifierSide::Right) => *KEY_RIGHT_ALT,
+            (Modifier::Alt, _) => *KEY_LEFT_ALT,
+            (Modifier::Meta, ModifierSide::Right) => *KEY_RIGHT_META,
+            (Modifier::Meta, _) => *KEY_LEFT_META,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct KeyModifierFlags {
     pub ctrl: bool,
     pub shift: bool,
     pub alt: bool,
     pub meta: bool,
+    pub ctrl_side: ModifierSide,
+    pub shift_side: ModifierSide,
+    pub alt_side: ModifierSide,
+    pub meta_side: ModifierSide,
+    /// matches regardless of extra modifiers when used in a trigger, passes the unmatched
+    /// modifiers through when used in an output
+    pub wildcard: bool,
 }
 
 impl KeyModifierFlags {
-    pub fn new() -> Self { KeyModifierFlags { ctrl: false, shift: false, alt: false, meta: false } }
+    pub fn new() -> Self {
+        KeyModifierFlags {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            meta: false,
+            ctrl_side: ModifierSide::Any,
+            shift_side: ModifierSide::Any,
+            alt_side: ModifierSide::Any,
+            meta_side: ModifierSide::Any,
+            wildcard: false,
+        }
+    }
     pub fn ctrl(&mut self) { self.ctrl = true; }
     pub fn alt(&mut self) { self.alt = true; }
     pub fn shift(&mut self) { self.shift = true; }
@@ -49,10 +107,86 @@ impl KeyModifierFlags {
         self.meta = true;
     }
     pub fn apply_from(&mut self, other: &KeyModifierFlags) {
-        if other.ctrl { self.ctrl(); }
-        if other.alt { self.alt(); }
-        if other.shift { self.shift(); }
-        if other.meta { self.meta(); }
+        for modifier in Modifier::ALL.iter() {
+            if other.is_set(*modifier) { self.set(*modifier, other.side(*modifier)); }
+        }
+        if other.wildcard { self.wildcard = true; }
+    }
+
+    pub fn is_set(&self, modifier: Modifier) -> bool {
+        match modifier {
+            Modifier::Ctrl => self.ctrl,
+            Modifier::Shift => self.shift,
+            Modifier::Alt => self.alt,
+            Modifier::Meta => self.meta,
+        }
+    }
+
+    pub fn side(&self, modifier: Modifier) -> ModifierSide {
+        match modifier {
+            Modifier::Ctrl => self.ctrl_side,
+            Modifier::Shift => self.shift_side,
+            Modifier::Alt => self.alt_side,
+            Modifier::Meta => self.meta_side,
+        }
+    }
+
+    pub fn set(&mut self, modifier: Modifier, side: ModifierSide) {
+        match modifier {
+            Modifier::Ctrl => { self.ctrl = true; self.ctrl_side = side; }
+            Modifier::Shift => { self.shift = true; self.shift_side = side; }
+            Modifier::Alt => { self.alt = true; self.alt_side = side; }
+            Modifier::Meta => { self.meta = true; self.meta_side = side; }
+        }
+    }
+
+    /// The key that gets pressed for a modifier, the left-hand variant unless the right-hand side
+    /// was requested.
+    pub fn key(&self, modifier: Modifier) -> Key { modifier.key(self.side(modifier)) }
+    pub fn ctrl_key(&self) -> Key { self.key(Modifier::Ctrl) }
+    pub fn shift_key(&self) -> Key { self.key(Modifier::Shift) }
+    pub fn alt_key(&self) -> Key { self.key(Modifier::Alt) }
+    pub fn meta_key(&self) -> Key { self.key(Modifier::Meta) }
+
+    /// Presses or releases the keys of all set modifiers.
+    pub fn modifier_key_actions(&self, value: i32) -> Vec<KeyAction> {
+        Modifier::ALL.iter()
+            .filter(|modifier| self.is_set(**modifier))
+            .map(|modifier| KeyAction::new(self.key(*modifier), value))
+            .collect()
+    }
+
+    /// Whether a modifier held down on the given side satisfies the flags.
+    pub fn accepts(&self, modifier: Modifier, side: ModifierSide) -> bool {
+        self.is_set(modifier) && (self.side(modifier) == ModifierSide::Any || self.side(modifier) == side)
+    }
+
+    /// Whether a modifier needs to be pressed to get from these flags to the `to` flags.
+    pub fn needs_press(&self, to: &KeyModifierFlags, modifier: Modifier) -> bool {
+        to.is_set(modifier) && !(self.is_set(modifier) &&
+            (to.side(modifier) == ModifierSide::Any || to.side(modifier) == self.side(modifier)))
+    }
+}
+
+impl fmt::Display for KeyModifierFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.wildcard { write!(f, "*")?; }
+        for modifier in Modifier::ALL.iter() {
+            if !self.is_set(*modifier) { continue; }
+            match self.side(*modifier) {
+                ModifierSide::Left => write!(f, "<")?,
+                ModifierSide::Right => write!(f, ">")?,
+                ModifierSide::Any => {}
+            }
+            let flag = match modifier {
+                Modifier::Ctrl => '^',
+                Modifier::Shift => '+',
+                Modifier::Alt => '!',
+                Modifier::Meta => '#',
+            };
+            write!(f, "{}", flag)?;
+        }
+        Ok(())
     }
 }
 
@@ -85,6 +219,67 @@ impl KeyModifierState {
     pub fn is_alt(&self) -> bool { self.left_alt || self.right_alt }
     pub fn is_shift(&self) -> bool { self.left_shift || self.right_shift }
     pub fn is_meta(&self) -> bool { self.left_meta || self.right_meta }
+
+    fn sides(&self, modifier: Modifier) -> (bool, bool) {
+        match modifier {
+            Modifier::Ctrl => (self.left_ctrl, self.right_ctrl),
+            Modifier::Shift => (self.left_shift, self.right_shift),
+            Modifier::Alt => (self.left_alt, self.right_alt),
+            Modifier::Meta => (self.left_meta, self.right_meta),
+        }
+    }
+
+    /// Returns every set of modifier flags that matches this state, side-specific flags first.
+    pub fn matching_flags(&self) -> Vec<KeyModifierFlags> {
+        self.flag_combinations(false)
+    }
+
+    /// Returns every set of wildcard modifier flags that matches this state, flags that cover more
+    /// of the held modifiers first.
+    pub fn wildcard_flags(&self) -> Vec<KeyModifierFlags> {
+        self.flag_combinations(true).into_iter()
+            .map(|mut flags| {
+                flags.wildcard = true;
+                flags
+            })
+            .collect()
+    }
+
+    fn flag_combinations(&self, allow_unset: bool) -> Vec<KeyModifierFlags> {
+        // (flags, modifier count, side-specific modifier count)
+        let mut candidates = vec![(KeyModifierFlags::new(), 0, 0)];
+
+        for modifier in Modifier::ALL.iter() {
+            let (left, right) = self.sides(*modifier);
+            let mut sides = vec![];
+            if left { sides.push(Some(ModifierSide::Left)); }
+            if right { sides.push(Some(ModifierSide::Right)); }
+            if sides.is_empty() { continue; }
+            sides.push(Some(ModifierSide::Any));
+            if allow_unset { sides.push(None); }
+
+            candidates = candidates.into_iter()
+                .flat_map(|(flags, count, specificity)| sides.iter().map(move |side| {
+                    let mut flags = flags;
+                    match side {
+                        Some(ModifierSide::Any) => {
+                            flags.set(*modifier, ModifierSide::Any);
+                            (flags, count + 1, specificity)
+                        }
+                        Some(side) => {
+                            flags.set(*modifier, *side);
+                            (flags, count + 1, specificity + 1)
+                        }
+                        None => (flags, count, specificity),
+                    }
+                }))
+                .collect();
+        }
+
+        // the sort is stable, so candidates with the same priority keep their order
+        candidates.sort_by(|a, b| (b.1, b.2).cmp(&(a.1, a.2)));
+        candidates.into_iter().map(|(flags, _, _)| flags).collect()
+    }
 }
 
 
@@ -111,6 +306,14 @@ pub struct KeyActionWithMods {
 
 impl KeyActionWithMods {
     pub fn new(key: Key, value: i32, modifiers: KeyModifierFlags) -> Self { KeyActionWithMods { key, value, modifiers } }
+
+    /// The action wrapped in presses and releases of its modifiers.
+    pub fn to_key_actions(&self) -> Vec<KeyAction> {
+        let mut actions = self.modifiers.modifier_key_actions(TYPE_DOWN);
+        actions.push(KeyAction::new(self.key, self.value));
+        actions.append(&mut self.modifiers.modifier_key_actions(TYPE_UP));
+        actions
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -123,6 +326,27 @@ impl KeyClickActionWithMods {
     pub fn new(key: Key) -> Self { KeyClickActionWithMods { key, modifiers: KeyModifierFlags::new() } }
     pub fn new_with_mods(key: Key, modifiers: KeyModifierFlags) -> Self { KeyClickActionWithMods { key, modifiers } }
     pub fn to_key_action(self, value: i32) -> KeyActionWithMods { KeyActionWithMods::new(self.key, value, self.modifiers) }
+
+    /// Presses the modifiers and then the key.
+    pub fn down_actions(&self) -> Vec<KeyAction> {
+        let mut actions = self.modifiers.modifier_key_actions(TYPE_DOWN);
+        actions.push(KeyAction::new(self.key, TYPE_DOWN));
+        actions
+    }
+
+    /// Releases the key and then the modifiers.
+    pub fn up_actions(&self) -> Vec<KeyAction> {
+        let mut actions = vec![KeyAction::new(self.key, TYPE_UP)];
+        actions.append(&mut self.modifiers.modifier_key_actions(TYPE_UP));
+        actions
+    }
+
+    /// A full click, the key gets pressed and released while the modifiers are held down.
+    pub fn to_key_actions(&self) -> Vec<KeyAction> {
+        let mut actions = self.down_actions();
+        actions.append(&mut self.up_actions());
+        actions
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -134,3 +358,56 @@ pub struct KeyMapping {
 pub static TYPE_UP: i32 = 0;
 pub static TYPE_DOWN: i32 = 1;
 pub static TYPE_REPEAT: i32 = 2;
+
+
+#[cfg(test)]
+mod tests {
+    use tap::Tap;
+
+    use super::*;
+
+    #[test]
+    fn test_matching_flags() {
+        let mut state = KeyModifierState::new();
+        assert_eq!(state.matching_flags(), vec![KeyModifierFlags::new()]);
+
+        state.right_alt = true;
+        state.left_ctrl = true;
+        assert_eq!(state.matching_flags(), vec![
+            KeyModifierFlags::new().tap_mut(|v| {
+                v.set(Modifier::Ctrl, ModifierSide::Left);
+                v.set(Modifier::Alt, ModifierSide::Right);
+            }),
+            KeyModifierFlags::new().tap_mut(|v| {
+                v.set(Modifier::Ctrl, ModifierSide::Left);
+                v.alt();
+            }),
+            KeyModifierFlags::new().tap_mut(|v| {
+                v.ctrl();
+                v.set(Modifier::Alt, ModifierSide::Right);
+            }),
+            KeyModifierFlags::new().tap_mut(|v| {
+                v.ctrl();
+                v.alt();
+            }),
+        ]);
+    }
+
+    #[test]
+    fn test_wildcard_flags() {
+        let mut state = KeyModifierState::new();
+        state.left_shift = true;
+
+        assert_eq!(state.wildcard_flags(), vec![
+            KeyModifierFlags::new().tap_mut(|v| {
+                v.set(Modifier::Shift, ModifierSide::Left);
+                v.wildcard = true;
+            }),
+            KeyModifierFlags::new().tap_mut(|v| {
+                v.shift();
+                v.wildcard = true;
+            }),
+            KeyModifierFlags::new().tap_mut(|v| v.wildcard = true),
+        ]);
+    }
+}
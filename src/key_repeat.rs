@@ -0,0 +1,142 @@
+use crate::*;
+
+/// How a click mapping behaves while its key is held down.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyRepeat {
+    /// The kernel's repeat events repeat the target.
+    Pass,
+    /// Repeat events are dropped, the target runs once per press.
+    Suppress,
+    /// The kernel's repeat events are dropped and the target is repeated with our own timing.
+    Software(SoftwareRepeat),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoftwareRepeat {
+    /// how long the key needs to be held before it starts repeating
+    pub delay: time::Duration,
+    /// the time between two repeats
+    pub interval: time::Duration,
+}
+
+/// Tracks the key that is repeated in software, like the kernel only the last pressed key repeats.
+pub struct KeyRepeatState {
+    /// the last pressed key and the id of the press, as long as it's held
+    pressed: Option<(Key, usize)>,
+    active: Option<(Key, usize, SoftwareRepeat)>,
+    next_id: usize,
+}
+
+impl KeyRepeatState {
+    pub fn new() -> Self { KeyRepeatState { pressed: None, active: None, next_id: 0 } }
+
+    /// Pressing a key stops the repeat of the previous one, the same as with the kernel's repeat.
+    /// Returns the id of the press.
+    pub fn press(&mut self, key: Key) -> usize {
+        self.next_id += 1;
+        self.pressed = Some((key, self.next_id));
+        self.active = None;
+        self.next_id
+    }
+
+    /// Stops repeating the key, if it's the one that repeats.
+    pub fn release(&mut self, key: Key) {
+        if matches!(self.pressed, Some((pressed, _)) if pressed == key) { self.pressed = None; }
+        if self.is_repeating(key) { self.active = None; }
+    }
+
+    /// The id of the press if the key is the last pressed key and still held.
+    pub fn pressed(&self, key: Key) -> Option<usize> {
+        match self.pressed {
+            Some((pressed, id)) if pressed == key => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Starts repeating the key of a press once its mapping was chosen, nothing happens if the key
+    /// was released or another key was pressed in the meantime. Returns the id of the first tick
+    /// and when it's due.
+    pub fn start(&mut self, press: usize, repeat: SoftwareRepeat) -> Option<(usize, time::Duration)> {
+        let key = match self.pressed {
+            Some((key, id)) if id == press => key,
+            _ => return None,
+        };
+
+        self.next_id += 1;
+        self.active = Some((key, self.next_id, repeat));
+        Some((self.next_id, repeat.delay))
+    }
+
+    pub fn is_repeating(&self, key: Key) -> bool {
+        matches!(self.active, Some((active, _, _)) if active == key)
+    }
+
+    /// Returns the key that needs to repeat and when the next tick is due, ticks of stopped
+    /// repeats are ignored.
+    pub fn tick(&self, id: usize) -> Option<(Key, time::Duration)> {
+        match self.active {
+            Some((key, active_id, repeat)) if active_id == id => Some((key, repeat.interval)),
+            _ => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repeat() -> SoftwareRepeat {
+        SoftwareRepeat { delay: time::Duration::from_millis(200), interval: time::Duration::from_millis(30) }
+    }
+
+    #[test]
+    fn test_repeat() {
+        let mut state = KeyRepeatState::new();
+        let press = state.press(*KEY_A);
+        let (id, delay) = state.start(press, repeat()).unwrap();
+        assert_eq!(delay, time::Duration::from_millis(200));
+
+        assert_eq!(state.tick(id), Some((*KEY_A, time::Duration::from_millis(30))));
+        assert_eq!(state.tick(id), Some((*KEY_A, time::Duration::from_millis(30))));
+
+        // releasing another key doesn't stop the repeat
+        state.release(*KEY_B);
+        assert!(state.is_repeating(*KEY_A));
+
+        state.release(*KEY_A);
+        assert_eq!(state.tick(id), None);
+    }
+
+    #[test]
+    fn test_restart() {
+        let mut state = KeyRepeatState::new();
+        let press = state.press(*KEY_A);
+        let (first, _) = state.start(press, repeat()).unwrap();
+        state.release(*KEY_A);
+        let press = state.press(*KEY_A);
+        let (second, _) = state.start(press, repeat()).unwrap();
+
+        // ticks that were scheduled before the key was released again are dropped
+        assert_eq!(state.tick(first), None);
+        assert!(state.tick(second).is_some());
+    }
+
+    #[test]
+    fn test_late_start() {
+        let mut state = KeyRepeatState::new();
+
+        // the mapping decided to repeat after the key was already released
+        let press = state.press(*KEY_A);
+        state.release(*KEY_A);
+        assert_eq!(state.start(press, repeat()), None);
+
+        // or after another key was pressed
+        let press = state.press(*KEY_A);
+        assert_eq!(state.pressed(*KEY_A), Some(press));
+        state.press(*KEY_B);
+        assert_eq!(state.pressed(*KEY_A), None);
+        assert_eq!(state.start(press, repeat()), None);
+        assert!(!state.is_repeating(*KEY_A));
+    }
+}
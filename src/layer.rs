@@ -0,0 +1,198 @@
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq)]
+enum LayerActivation {
+    /// active until turned off explicitly
+    On,
+    /// active until the next non-modifier key press was looked up
+    OneShot,
+    /// active while the given key is held down
+    Momentary(Key),
+}
+
+#[derive(Debug, Clone)]
+struct ActiveLayer {
+    name: String,
+    activation: LayerActivation,
+}
+
+/// Named mapping tables that can be stacked on top of the base mappings.
+pub struct LayerState {
+    pub layers: HashMap<String, CompiledKeyMappings>,
+    /// keys that activate a layer while held down
+    pub momentary: HashMap<Key, String>,
+    // the last element is the topmost layer
+    stack: Vec<ActiveLayer>,
+    // the layer that handled each held key or `None` for the base mappings, the remaining events of
+    // a key are looked up in the same place
+    pressed: HashMap<Key, Option<String>>,
+}
+
+impl LayerState {
+    pub fn new() -> Self {
+        LayerState { layers: Default::default(), momentary: Default::default(), stack: vec![], pressed: Default::default() }
+    }
+
+    pub fn add_mapping(&mut self, layer: String, from: KeyActionWithMods, target: MappingTarget) {
+        self.layers.entry(layer).or_insert_with(CompiledKeyMappings::new).insert(from, target);
+    }
+
+    pub fn is_active(&self, name: &str) -> bool {
+        self.stack.iter().any(|v| v.name == name)
+    }
+
+    fn push(&mut self, name: String, activation: LayerActivation) {
+        // re-activating a layer moves it to the top of the stack
+        self.stack.retain(|v| v.name != name || v.activation != activation);
+        self.stack.push(ActiveLayer { name, activation });
+    }
+
+    pub fn layer_on(&mut self, name: String) { self.push(name, LayerActivation::On); }
+
+    pub fn layer_one_shot(&mut self, name: String) { self.push(name, LayerActivation::OneShot); }
+
+    pub fn layer_off(&mut self, name: &str) {
+        self.stack.retain(|v| v.name != name);
+    }
+
+    pub fn layer_toggle(&mut self, name: String) {
+        if self.is_active(&name) { self.layer_off(&name); } else { self.layer_on(name); }
+    }
+
+    /// Activates or deactivates momentary layers, returns true if the event was consumed.
+    pub fn handle_momentary(&mut self, action: &KeyAction) -> bool {
+        let name = match self.momentary.get(&action.key) {
+            Some(name) => name.clone(),
+            None => return false,
+        };
+
+        let activation = LayerActivation::Momentary(action.key);
+        if action.value == TYPE_DOWN {
+            self.push(name, activation);
+        } else if action.value == TYPE_UP {
+            self.stack.retain(|v| v.activation != activation);
+        }
+        true
+    }
+
+    fn get(&self, layer: &str, candidates: &[KeyActionWithMods]) -> Option<Arc<Vec<MappingTarget>>> {
+        let mappings = self.layers.get(layer)?;
+        candidates.iter().find_map(|action| mappings.0.get(action)).cloned()
+    }
+
+    /// Looks up a mapping by walking the active layers from the top down, falling through to the
+    /// base mappings. The candidates are different variants of the same event, ordered by priority.
+    pub fn lookup(&mut self, candidates: &[KeyActionWithMods], base: &CompiledKeyMappings) -> Option<Arc<Vec<MappingTarget>>> {
+        let (key, value) = match candidates.first() {
+            Some(action) => (action.key, action.value),
+            None => return None,
+        };
+
+        if value != TYPE_DOWN {
+            let pressed_layer = if value == TYPE_UP {
+                self.pressed.remove(&key)
+            } else {
+                self.pressed.get(&key).cloned()
+            };
+
+            match pressed_layer {
+                Some(Some(layer)) => return self.get(&layer, candidates),
+                Some(None) => return candidates.iter().find_map(|action| base.0.get(action)).cloned(),
+                // pressed before the layers were known
+                None => {}
+            }
+        }
+
+        let found = self.stack.iter().rev()
+            .find_map(|layer| self.get(&layer.name, candidates).map(|mapping| (layer.name.clone(), mapping)));
+
+        if value == TYPE_DOWN && !key.is_modifier() {
+            self.stack.retain(|v| v.activation != LayerActivation::OneShot);
+        }
+
+        let (layer, mapping) = match found {
+            Some((layer, mapping)) => (Some(layer), Some(mapping)),
+            None => (None, candidates.iter().find_map(|action| base.0.get(action)).cloned()),
+        };
+        if value == TYPE_DOWN { self.pressed.insert(key, layer); }
+        mapping
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> MappingTarget {
+        MappingTarget { id: 0, guard: None, window: None, repeat: None, block: Block::new(), var_map: Environment::new() }
+    }
+
+    fn action(key: Key, value: i32) -> KeyActionWithMods { KeyActionWithMods::new(key, value, KeyModifierFlags::new()) }
+
+    fn state() -> (LayerState, CompiledKeyMappings, Arc<Vec<MappingTarget>>, Arc<Vec<MappingTarget>>) {
+        let mut state = LayerState::new();
+        let nav = Arc::new(vec![mapping()]);
+        state.layers.insert("nav".to_string(), CompiledKeyMappings::new());
+        state.layers.get_mut("nav").unwrap().0.insert(action(*KEY_H, TYPE_DOWN), nav.clone());
+        state.layers.get_mut("nav").unwrap().0.insert(action(*KEY_H, TYPE_UP), nav.clone());
+
+        let mut base = CompiledKeyMappings::new();
+        let base_mapping = Arc::new(vec![mapping()]);
+        base.0.insert(action(*KEY_H, TYPE_DOWN), base_mapping.clone());
+        base.0.insert(action(*KEY_H, TYPE_UP), base_mapping.clone());
+        (state, base, nav, base_mapping)
+    }
+
+    #[test]
+    fn test_layer_fallthrough() {
+        let (mut state, base, nav, base_mapping) = state();
+
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_DOWN)], &base).unwrap(), &base_mapping));
+
+        state.layer_on("nav".to_string());
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_DOWN)], &base).unwrap(), &nav));
+        assert!(state.lookup(&[action(*KEY_J, TYPE_DOWN)], &base).is_none());
+
+        state.layer_toggle("nav".to_string());
+        assert!(!state.is_active("nav"));
+    }
+
+    #[test]
+    fn test_layer_one_shot() {
+        let (mut state, base, nav, base_mapping) = state();
+
+        state.layer_one_shot("nav".to_string());
+        assert!(state.lookup(&[action(*KEY_LEFT_SHIFT, TYPE_DOWN)], &base).is_none());
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_DOWN)], &base).unwrap(), &nav));
+        assert!(!state.is_active("nav"));
+
+        // the release still belongs to the layer that handled the press
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_UP)], &base).unwrap(), &nav));
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_DOWN)], &base).unwrap(), &base_mapping));
+    }
+
+    #[test]
+    fn test_layer_momentary() {
+        let (mut state, base, nav, _) = state();
+        state.momentary.insert(*KEY_CAPSLOCK, "nav".to_string());
+
+        assert!(state.handle_momentary(&KeyAction::new(*KEY_CAPSLOCK, TYPE_DOWN)));
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_DOWN)], &base).unwrap(), &nav));
+        assert!(state.handle_momentary(&KeyAction::new(*KEY_CAPSLOCK, TYPE_UP)));
+        assert!(!state.is_active("nav"));
+        assert!(!state.handle_momentary(&KeyAction::new(*KEY_H, TYPE_UP)));
+    }
+
+    #[test]
+    fn test_layer_activated_while_held() {
+        let (mut state, base, _, base_mapping) = state();
+        state.momentary.insert(*KEY_CAPSLOCK, "nav".to_string());
+
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_DOWN)], &base).unwrap(), &base_mapping));
+        state.handle_momentary(&KeyAction::new(*KEY_CAPSLOCK, TYPE_DOWN));
+
+        // the release goes to the base mappings that handled the press
+        assert!(Arc::ptr_eq(&state.lookup(&[action(*KEY_H, TYPE_UP)], &base).unwrap(), &base_mapping));
+    }
+}
@@ -18,13 +18,26 @@ pub use tokio::prelude::*;
 pub use tokio::sync::{mpsc, oneshot};
 pub use tokio::task;
 
-pub use crate::cli::parse_cli;
+pub use crate::cli::{parse_cli, Command};
 pub use crate::device::virtual_input_device::bind_udev_inputs;
 pub use crate::key_defs::*;
 pub use crate::key_primitives::*;
 pub use crate::runtime::*;
 pub use crate::runtime::evaluation::*;
+pub use crate::runtime::error::*;
+pub use crate::runtime::environment::Environment;
+pub use crate::runtime::concurrency::{ScriptTask, ScriptChannel};
 pub use crate::state::*;
+pub use crate::tap_hold::*;
+pub use crate::chord::*;
+pub use crate::sequence_trigger::*;
+pub use crate::layer::*;
+pub use crate::sticky_modifiers::*;
+pub use crate::window_condition::*;
+pub use crate::timer::*;
+pub use crate::handler_queue::*;
+pub use crate::key_repeat::*;
+pub use crate::rel_mapping::*;
 pub use crate::x11::{x11_initialize, get_window_info_x11};
 pub use crate::x11::ActiveWindowInfo;
 
@@ -33,6 +46,7 @@ pub mod key_defs;
 pub mod state;
 pub mod runtime;
 pub mod script;
+pub mod import;
 pub mod block_ext;
 pub mod key_primitives;
 pub mod parsing;
@@ -42,6 +56,17 @@ pub mod ignore_list;
 pub mod messaging;
 pub mod event_handlers;
 pub mod logging;
+pub mod tap_hold;
+pub mod chord;
+pub mod sequence_trigger;
+pub mod layer;
+pub mod sticky_modifiers;
+pub mod window_condition;
+pub mod timer;
+pub mod handler_queue;
+pub mod key_repeat;
+pub mod rel_mapping;
+pub mod check;
 
 #[cfg(test)]
 pub mod tests;
@@ -6,7 +6,10 @@ mod event_handlers;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let mut configuration = parse_cli()?;
+    let mut configuration = match parse_cli()? {
+        Command::Run(configuration) => configuration,
+        Command::Check(script_path) => std::process::exit(check(&script_path)),
+    };
 
     // create X11 communication channels
     let (window_ev_tx, mut window_ev_rx) = mpsc::channel(128);
@@ -30,12 +33,13 @@ async fn main() -> Result<()> {
 
     // initialize global state
     let mut stdout = io::stdout();
+    let mut stderr = io::stderr();
     let mut state = State::new();
     let mut window_cycle_token: usize = 0;
     let mut mappings = CompiledKeyMappings::new();
     let mut window_change_handlers = vec![];
 
-    let script_ast = script::parse_script(&mut configuration.script_file);
+    let script_ast = script::parse_script(&mut configuration.script_file, &configuration.script_path)?;
 
     // add a small delay if run from TTY so we don't miss 'enter up' which is often released when the device is grabbed
     if atty::is(atty::Stream::Stdout) {
@@ -79,9 +83,35 @@ async fn main() -> Result<()> {
                 ).await.unwrap();
             }
             Some(msg) = message_rx.recv() => {
-                event_handlers::handle_execution_message(&mut stdout, window_cycle_token, msg, &mut state,
-                    &mut mappings, &mut window_change_handlers).await;
+                let exit_code = event_handlers::handle_execution_message(&mut stdout, &mut stderr, window_cycle_token, msg,
+                    &mut state, &mut mappings, &mut window_change_handlers, &mut ev_reader_tx, &mut execution_message_tx,
+                    &configuration).await;
+                if let Some(exit_code) = exit_code { std::process::exit(exit_code) }
             }
         }
     }
 }
+
+/// Prints the problems found in the script, returns the exit code.
+fn check(script_path: &str) -> i32 {
+    let raw = match fs::read_to_string(script_path) {
+        Ok(raw) => raw,
+        Err(err) => {
+            eprintln!("error: failed to read script file '{}': {}", script_path, err);
+            return 2;
+        }
+    };
+
+    match check::check_script(&raw, script_path) {
+        Ok(problems) if problems.is_empty() => 0,
+        Ok(problems) => {
+            for problem in &problems { println!("{}", problem); }
+            println!("found {} problem(s)", problems.len());
+            1
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            2
+        }
+    }
+}
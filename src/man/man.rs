@@ -14,6 +14,10 @@ fn main() {
             .short("-d")
             .long("--devices")
         )
+        .option(Opt::new("policy")
+            .help("What to do on runtime errors: 'exit' (default) or 'continue' after logging the error")
+            .long("--on-error")
+        )
         .example(Example::new()
             .text("run a script")
             .command("map2 example.m2")
@@ -29,6 +33,11 @@ fn main() {
             .command("map2 -vvv example.m2")
             .output("Runs the script example.m2 and outputs all debug information.")
         )
+        .example(Example::new()
+            .text("check a script for problems without running it")
+            .command("map2 check example.m2")
+            .output("Reports undefined variables, wrong function arguments, invalid key sequences and unreachable or shadowed mappings along with their location.")
+        )
         .custom(
             Section::new("devices")
                 .paragraph(&*vec![
@@ -1,17 +1,41 @@
-use anyhow::Error;
-
 use crate::*;
 
 #[derive(Debug)]
 pub enum ExecutionMessage {
     // EatEv(KeyAction),
-    AddMapping(usize, KeyActionWithMods, Block, GuardedVarMap),
+    AddMapping(usize, Option<String>, KeyActionWithMods, MappingTarget),
+    RemoveMapping(Option<String>, Vec<KeyActionWithMods>),
+    RemoveMappingById(usize),
+    RemoveAllMappings(Option<String>),
+    GetMappings(Option<String>, mpsc::Sender<Vec<String>>),
+    AddTapHoldMapping(usize, usize, Key, TapHold),
+    TapHoldTimeout(usize),
+    AddChordMapping(usize, ChordMapping),
+    SetChordWindow(time::Duration),
+    ChordTimeout(usize),
+    AddSequenceMapping(usize, SequenceMapping),
+    AddRelMapping(usize, RelTrigger, MappingTarget),
+    SetLeader(KeyClickActionWithMods),
+    SetSequenceTimeout(time::Duration),
+    SetHandlerOrder(HandlerOrder),
+    SequenceTimeout(usize),
+    AddMomentaryLayer(usize, Key, String),
+    LayerOn(String),
+    LayerOff(String),
+    LayerToggle(String),
+    LayerOneShot(String),
+    SetStickyModifiers(bool, bool),
     GetFocusedWindowInfo(mpsc::Sender<Option<ActiveWindowInfo>>),
-    RegisterWindowChangeCallback(Block, GuardedVarMap),
+    RegisterWindowChangeCallback(Block, Environment),
+    AddTimer(usize, Timer),
+    ClearTimer(usize),
+    TimerFired(usize),
+    StartKeyRepeat(usize, SoftwareRepeat),
+    KeyRepeat(usize),
     Write(String),
     UpdateModifiers(KeyAction),
     Exit(i32),
-    FatalError(Error, i32),
+    RuntimeError(RuntimeError),
 }
 
 pub type ExecutionMessageSender = tokio::sync::mpsc::Sender<ExecutionMessage>;
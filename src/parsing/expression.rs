@@ -8,38 +8,60 @@ pub(super) fn expr_4(input: &str) -> ResNew<&str, Expr> {
         boolean,
         string,
         number,
+        list,
+        map_literal,
         lambda,
         variable_initialization,
         variable_assignment,
         function_call,
-        key_mapping,
-        key_mapping_inline,
+        key_mapping_tap_hold,
+        key_mapping_chord,
+        key_mapping_sequence,
+        rel_mapping,
+        key_mapping_suffixed,
         variable,
     ))(input)
 }
 
+fn expr_index(input: &str) -> ResNew<&str, Expr> {
+    let (input, (init, last_err)) = expr_4(input)?;
+    let (input, indices) = many0(index)(input)?;
+
+    if indices.is_empty() { return Ok((input, (init, last_err))); }
+    let expr = indices.into_iter().fold(init, |acc, (idx, _)| Expr::Index(Box::new(acc), Box::new(idx)));
+    Ok((input, (expr, None)))
+}
+
 pub(super) fn expr_3(input: &str) -> ResNew<&str, Expr> {
     // TODO fold this
     let (input, expr) = alt((
-        expr_4,
+        expr_index,
         map(
             tuple((tag_custom("!"), not(tag("{")), expr_3)),
             |(_, _, (expr, last_err))| (Expr::Neg(Box::new(expr)), last_err),
         ),
+        map(
+            tuple((tag_custom("-"), ws0, expr_3)),
+            |(_, _, (expr, last_err))| (Expr::Minus(Box::new(expr)), last_err),
+        ),
     ))(input)?;
 
     Ok((input, expr))
 }
 
-pub(super) fn expr_2(i: &str) -> ResNew<&str, Expr> {
-    let (input, init) = expr_3(i)?;
+pub(super) fn expr_2(input: &str) -> ResNew<&str, Expr> {
+    let (input, init) = expr_3(input)?;
     let expr = fold_many0_once_err(
         |input: &str| {
             tuple((
                 ws0,
-                alt((tag_custom("*"), tag_custom("/"))),
+                alt((
+                    tag_custom("*"),
+                    tag_custom("/"),
+                    tag_custom("%"),
+                )),
                 ws0,
-                expr_3
+                expr_3,
             ))(input)
         },
         init.0,
@@ -47,6 +69,7 @@ pub(super) fn expr_2(i: &str) -> ResNew<&str, Expr> {
             match op {
                 "*" => Expr::Mul(Box::new(acc), Box::new(val)),
                 "/" => Expr::Div(Box::new(acc), Box::new(val)),
+                "%" => Expr::Mod(Box::new(acc), Box::new(val)),
                 _ => unreachable!()
             }
         },
@@ -58,13 +81,16 @@ pub(super) fn expr_2(i: &str) -> ResNew<&str, Expr> {
     }
 }
 
-pub(super) fn expr_1(i: &str) -> ResNew<&str, Expr> {
-    let (input, init) = expr_2(i)?;
+pub(super) fn expr_1(input: &str) -> ResNew<&str, Expr> {
+    let (input, init) = expr_2(input)?;
     let expr = fold_many0_once_err(
         |input: &str| {
             tuple((
                 ws0,
-                alt((tag_custom("+"), tag_custom("-"))),
+                alt((
+                    tag_custom("+"),
+                    tag_custom("-"),
+                )),
                 ws0,
                 expr_2,
             ))(input)
@@ -85,31 +111,113 @@ pub(super) fn expr_1(i: &str) -> ResNew<&str, Expr> {
     }
 }
 
-pub(super) fn expr(input: &str) -> ResNew<&str, Expr> {
+fn expr_comparison(input: &str) -> ResNew<&str, Expr> {
     let (input, init) = expr_1(input)?;
+    let expr = fold_many0_once_err(
+        |input: &str| {
+            tuple((
+                ws0,
+                alt((
+                    tag_custom("<="),
+                    tag_custom(">="),
+                    tag_custom("<"),
+                    tag_custom(">"),
+                )),
+                ws0,
+                expr_1,
+            ))(input)
+        },
+        init.0,
+        |acc, (_, op, _, (val, _))| {
+            match op {
+                "<=" => Expr::LE(Box::new(acc), Box::new(val)),
+                ">=" => Expr::GE(Box::new(acc), Box::new(val)),
+                "<" => Expr::LT(Box::new(acc), Box::new(val)),
+                ">" => Expr::GT(Box::new(acc), Box::new(val)),
+                _ => unreachable!()
+            }
+        },
+    )(input);
 
+    match expr {
+        Err(v) => Err(v),
+        Ok((next, (expr, last_err))) => Ok((next, (expr, Some(last_err)))),
+    }
+}
+
+fn expr_equality(input: &str) -> ResNew<&str, Expr> {
+    let (input, init) = expr_comparison(input)?;
     let expr = fold_many0_once_err(
-        |i: &str| {
+        |input: &str| {
             tuple((
                 ws0,
                 alt((
                     tag_custom("=="),
                     tag_custom("!="),
-                    tag_custom("&&"),
-                    tag_custom("||"),
-                    tag_custom("<"),
-                    tag_custom(">"))),
-                ws0, expr_1))(i)
+                )),
+                ws0,
+                expr_comparison,
+            ))(input)
         },
         init.0,
         |acc, (_, op, _, (val, _))| {
             match op {
                 "==" => Expr::Eq(Box::new(acc), Box::new(val)),
                 "!=" => Expr::Neq(Box::new(acc), Box::new(val)),
+                _ => unreachable!()
+            }
+        },
+    )(input);
+
+    match expr {
+        Err(v) => Err(v),
+        Ok((next, (expr, last_err))) => Ok((next, (expr, Some(last_err)))),
+    }
+}
+
+fn expr_and(input: &str) -> ResNew<&str, Expr> {
+    let (input, init) = expr_equality(input)?;
+    let expr = fold_many0_once_err(
+        |input: &str| {
+            tuple((
+                ws0,
+                tag_custom("&&"),
+                ws0,
+                expr_equality,
+            ))(input)
+        },
+        init.0,
+        |acc, (_, op, _, (val, _))| {
+            match op {
                 "&&" => Expr::And(Box::new(acc), Box::new(val)),
+                _ => unreachable!()
+            }
+        },
+    )(input);
+
+    match expr {
+        Err(v) => Err(v),
+        Ok((next, (expr, last_err))) => Ok((next, (expr, Some(last_err)))),
+    }
+}
+
+/// Operators from lowest to highest precedence: `||`, `&&`, `== !=`, `< > <= >=`,
+/// `+ -`, `* / %` and finally the unary `!` and `-`.
+pub(super) fn expr(input: &str) -> ResNew<&str, Expr> {
+    let (input, init) = expr_and(input)?;
+    let expr = fold_many0_once_err(
+        |input: &str| {
+            tuple((
+                ws0,
+                tag_custom("||"),
+                ws0,
+                expr_and,
+            ))(input)
+        },
+        init.0,
+        |acc, (_, op, _, (val, _))| {
+            match op {
                 "||" => Expr::Or(Box::new(acc), Box::new(val)),
-                ">" => Expr::GT(Box::new(acc), Box::new(val)),
-                "<" => Expr::LT(Box::new(acc), Box::new(val)),
                 _ => unreachable!()
             }
         },
@@ -159,4 +267,22 @@ mod tests {
                        Box::new(Expr::Value(ValueType::Number(33.0))),
                    )));
     }
+
+    #[test]
+    fn test_precedence() {
+        let num = |v| Box::new(Expr::Value(ValueType::Number(v)));
+        let name = |v: &str| Box::new(Expr::Name(v.to_string()));
+
+        assert_eq!(nom_no_last_err(expr("1 + 2 * 3 % 4")),
+                   nom_ok(Expr::Add(num(1.0), Box::new(Expr::Mod(Box::new(Expr::Mul(num(2.0), num(3.0))), num(4.0))))));
+
+        assert_eq!(nom_no_last_err(expr("a || b && c == 1")),
+                   nom_ok(Expr::Or(name("a"), Box::new(Expr::And(name("b"), Box::new(Expr::Eq(name("c"), num(1.0))))))));
+
+        assert_eq!(nom_no_last_err(expr("a <= 1 == b >= 2")),
+                   nom_ok(Expr::Eq(Box::new(Expr::LE(name("a"), num(1.0))), Box::new(Expr::GE(name("b"), num(2.0))))));
+
+        assert_eq!(nom_no_last_err(expr("-a * 2")),
+                   nom_ok(Expr::Mul(Box::new(Expr::Minus(name("a"))), num(2.0))));
+    }
 }
@@ -5,7 +5,7 @@ pub(super) fn function_arg(input: &str) -> ResNew<&str, Expr> {
 }
 
 pub(super) fn function_call(input: &str) -> ResNew<&str, Expr> {
-    let (input, (ident_res,_)) = tuple((ident, tag_custom("(")))(input)
+    let (input, (ident_res,_)) = tuple((qualified_ident, tag_custom("(")))(input)
         .map_err(|_: NomErr<CustomError<_>>| make_generic_nom_err_options(input, vec!["function call".to_string()]))?;
 
     tuple((
@@ -33,6 +33,38 @@ pub(super) fn function_call(input: &str) -> ResNew<&str, Expr> {
         (next, (expr,None))
     })
 }
+/// `fn name(a, b) { ... }`
+pub(super) fn function_declaration(input: &str) -> ResNew<&str, Stmt> {
+    let (input, _) = tuple((tag_custom("fn"), ws1))(input)?;
+
+    tuple((
+        ident, ws0,
+        tag_custom("("), ws0,
+        opt(tuple((
+            ident,
+            ws0,
+            many0(tuple((
+                tag_custom(","),
+                ws0,
+                ident,
+                ws0,
+            ))),
+        ))),
+        tag_custom(")"), ws0,
+        block,
+    ))(input)
+        .map(|(next, v)| {
+            let params = match v.4 {
+                Some(params) => std::iter::once(params.0.0)
+                    .chain(params.2.into_iter().map(|v| v.2.0))
+                    .collect(),
+                None => vec![],
+            };
+
+            let (block, last_err) = v.7;
+            (next, (Stmt::Function(v.0.0, params, block), last_err))
+        })
+}
 
 #[cfg(test)]
 mod tests {
@@ -53,4 +85,15 @@ mod tests {
             nom_eval(variable("variable"))
         ])));
     }
+
+    #[test]
+    fn test_function_declaration() {
+        assert_eq!(function_declaration("fn foo() {}"), nom_ok(
+            Stmt::Function("foo".to_string(), vec![], Block::new())
+        ));
+        assert_eq!(function_declaration("fn sum(a, b){ return a + b; }"), nom_ok(
+            Stmt::Function("sum".to_string(), vec!["a".to_string(), "b".to_string()], nom_eval(block("{ return a + b; }")))
+        ));
+        assert!(matches!(function_declaration("fn(a) {}"), Err(..)));
+    }
 }
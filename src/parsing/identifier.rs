@@ -9,13 +9,30 @@ pub(super) fn ident(input: &str) -> ResNew<&str, String> {
     };
 
     match id.0.as_ref() {
-        "break" | "continue" | "do" | "else" | "false" | "for" |
-        "if" | "in" | "let" | "loop" | "return" | "true" | "while"
+        "break" | "continue" | "do" | "else" | "false" | "fn" | "for" |
+        "if" | "import" | "in" | "let" | "loop" | "return" | "true" | "while"
         => Err(make_generic_nom_err_new(input)),
         _ => Ok((rest, id)),
     }
 }
 
+/// An identifier that may be qualified with the namespace of an import, e.g. `common.greet`.
+pub(super) fn qualified_ident(input: &str) -> ResNew<&str, String> {
+    let (mut rest, (mut name, _)) = ident(input)?;
+
+    while rest.starts_with('.') && !rest[1..].starts_with(char::is_whitespace) {
+        match ident(&rest[1..]) {
+            Ok((next, (part, _))) => {
+                name = format!("{}.{}", name, part);
+                rest = next;
+            }
+            Err(_) => break,
+        }
+    }
+
+    Ok((rest, (name, None)))
+}
+
 pub(super) fn word(input: &str) -> ResNew<&str, String> {
     let (input, _) = ws0(input)?;
 
@@ -43,5 +60,7 @@ mod tests {
         assert_eq!(ident("_foobar"), nom_ok("_foobar".to_string()));
         assert_eq!(ident("btn_forward"), nom_ok("btn_forward".to_string()));
         assert_eq!(ident("foo.bar"), nom_ok_rest(".bar", "foo".to_string()));
+        assert_eq!(qualified_ident("foo.bar.baz"), nom_ok("foo.bar.baz".to_string()));
+        assert_eq!(qualified_ident("foo. bar"), nom_ok_rest(". bar", "foo".to_string()));
     }
 }
@@ -0,0 +1,35 @@
+use super::*;
+
+/// `import "path";` or `import "path" as name;`
+pub(super) fn import_stmt(input: &str) -> ResNew<&str, Stmt> {
+    let (input, _) = tag_custom("import")(input)?;
+
+    tuple((
+        ws1,
+        string_value,
+        opt(tuple((ws1, tag_custom("as"), ws1, ident))),
+        ws0,
+        tag_custom(";"),
+    ))(input)
+        .map(|(next, (_, (path, _), namespace, _, _))| {
+            let namespace = namespace.map(|(_, _, _, (name, _))| name);
+            (next, (Stmt::Import(path, namespace), None))
+        })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        assert_eq!(import_stmt("import \"lib/common.m2\";"), nom_ok(
+            Stmt::Import("lib/common.m2".to_string(), None)
+        ));
+        assert_eq!(import_stmt("import \"lib/common.m2\" as common;"), nom_ok(
+            Stmt::Import("lib/common.m2".to_string(), Some("common".to_string()))
+        ));
+        assert!(matches!(import_stmt("import lib;"), Err(..)));
+    }
+}
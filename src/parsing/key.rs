@@ -1,16 +1,23 @@
 use super::*;
 
 pub(super) fn key_flags(input: &str) -> ResNew<&str, KeyModifierFlags> {
-    many0(one_of("^!+#"))(input).and_then(|(next, val)| {
+    many0(tuple((opt(one_of("<>")), one_of("^!+#"))))(input).and_then(|(next, val)| {
         let mut flags = KeyModifierFlags::new();
-        for v in val {
-            match v {
-                '!' => { if !flags.alt { flags.alt(); } else { return Err(make_generic_nom_err_new(input)); } }
-                '^' => { if !flags.ctrl { flags.ctrl(); } else { return Err(make_generic_nom_err_new(input)); } }
-                '+' => { if !flags.shift { flags.shift(); } else { return Err(make_generic_nom_err_new(input)); } }
-                '#' => { if !flags.meta { flags.meta(); } else { return Err(make_generic_nom_err_new(input)); } }
+        for (side, v) in val {
+            let modifier = match v {
+                '!' => Modifier::Alt,
+                '^' => Modifier::Ctrl,
+                '+' => Modifier::Shift,
+                '#' => Modifier::Meta,
                 _ => unreachable!()
-            }
+            };
+            let side = match side {
+                Some('<') => ModifierSide::Left,
+                Some('>') => ModifierSide::Right,
+                _ => ModifierSide::Any,
+            };
+            if flags.is_set(modifier) { return Err(make_generic_nom_err_new(input)); }
+            flags.set(modifier, side);
         };
         Ok((next, (flags, None)))
     })
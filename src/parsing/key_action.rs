@@ -15,33 +15,11 @@ impl ParsedKeyActionVecExt for Vec<ParsedKeyAction> {
     fn to_key_actions(self) -> Vec<KeyAction> {
         // TODO keep track of modifier keys and revert to a sane state after every action
         self.into_iter()
-            .fold(vec![], |mut acc, v| match v {
-                ParsedKeyAction::KeyAction(action) => {
-                    if action.modifiers.ctrl { acc.push(KeyAction::new(*KEY_LEFT_CTRL, TYPE_DOWN)); }
-                    if action.modifiers.shift { acc.push(KeyAction::new(*KEY_LEFT_SHIFT, TYPE_DOWN)); }
-                    if action.modifiers.alt { acc.push(KeyAction::new(*KEY_LEFT_ALT, TYPE_DOWN)); }
-                    if action.modifiers.meta { acc.push(KeyAction::new(*KEY_LEFT_META, TYPE_DOWN)); }
-                    acc.push(KeyAction::new(action.key, action.value));
-                    if action.modifiers.ctrl { acc.push(KeyAction::new(*KEY_LEFT_CTRL, TYPE_UP)); }
-                    if action.modifiers.shift { acc.push(KeyAction::new(*KEY_LEFT_SHIFT, TYPE_UP)); }
-                    if action.modifiers.alt { acc.push(KeyAction::new(*KEY_LEFT_ALT, TYPE_UP)); }
-                    if action.modifiers.meta { acc.push(KeyAction::new(*KEY_LEFT_META, TYPE_UP)); }
-                    acc
-                }
-                ParsedKeyAction::KeyClickAction(action) => {
-                    if action.modifiers.ctrl { acc.push(KeyAction::new(*KEY_LEFT_CTRL, TYPE_DOWN)); }
-                    if action.modifiers.shift { acc.push(KeyAction::new(*KEY_LEFT_SHIFT, TYPE_DOWN)); }
-                    if action.modifiers.alt { acc.push(KeyAction::new(*KEY_LEFT_ALT, TYPE_DOWN)); }
-                    if action.modifiers.meta { acc.push(KeyAction::new(*KEY_LEFT_META, TYPE_DOWN)); }
-                    acc.push(KeyAction::new(action.key, TYPE_DOWN));
-                    acc.push(KeyAction::new(action.key, TYPE_UP));
-                    if action.modifiers.ctrl { acc.push(KeyAction::new(*KEY_LEFT_CTRL, TYPE_UP)); }
-                    if action.modifiers.shift { acc.push(KeyAction::new(*KEY_LEFT_SHIFT, TYPE_UP)); }
-                    if action.modifiers.alt { acc.push(KeyAction::new(*KEY_LEFT_ALT, TYPE_UP)); }
-                    if action.modifiers.meta { acc.push(KeyAction::new(*KEY_LEFT_META, TYPE_UP)); }
-                    acc
-                }
+            .flat_map(|v| match v {
+                ParsedKeyAction::KeyAction(action) => action.to_key_actions(),
+                ParsedKeyAction::KeyClickAction(action) => action.to_key_actions(),
             })
+            .collect()
     }
 }
 
@@ -70,9 +48,12 @@ pub(super) fn key_action(input: &str) -> ResNew<&str, ParsedKeyAction> {
 }
 
 pub(super) fn key_action_with_flags(input: &str) -> ResNew<&str, ParsedKeyAction> {
-    tuple((
-        key_flags,
-        key_action,
+    alt((
+        // a leading '*' only acts as a wildcard if a key follows, otherwise it's the '*' key
+        map(tuple((char('*'), key_flags, key_action)), |(_, flags, action)| {
+            ((flags.0.tap_mut(|v| v.wildcard = true), None), action)
+        }),
+        tuple((key_flags, key_action)),
     ))(input).and_then(|(next, parts)| {
         let flags = parts.0;
         let mut action = parts.1;
@@ -111,5 +92,29 @@ mod tests {
         assert_eq!(key_action_with_flags("!{j down}"), nom_ok(ParsedKeyAction::KeyAction(
             KeyActionWithMods::new(Key::from_str(&EventType::EV_KEY, "KEY_J").unwrap(), 1, KeyModifierFlags::new().tap_mut(|v| v.alt()))
         )));
+
+        assert_eq!(key_action_with_flags("<^a"), nom_ok(ParsedKeyAction::KeyClickAction(
+            KeyClickActionWithMods::new_with_mods(*KEY_A, KeyModifierFlags::new().tap_mut(|v| v.set(Modifier::Ctrl, ModifierSide::Left)))
+        )));
+
+        assert_eq!(key_action_with_flags(">!+{j down}"), nom_ok(ParsedKeyAction::KeyAction(
+            KeyActionWithMods::new(*KEY_J, 1, KeyModifierFlags::new().tap_mut(|v| {
+                v.set(Modifier::Alt, ModifierSide::Right);
+                v.shift();
+            }))
+        )));
+
+        assert!(matches!(key_action_with_flags("<^>^a"), Err(..)));
+
+        assert_eq!(key_action_with_flags("*^a"), nom_ok(ParsedKeyAction::KeyClickAction(
+            KeyClickActionWithMods::new_with_mods(*KEY_A, KeyModifierFlags::new().tap_mut(|v| {
+                v.ctrl();
+                v.wildcard = true;
+            }))
+        )));
+
+        assert_eq!(key_action_with_flags("*"), nom_ok(ParsedKeyAction::KeyClickAction(
+            KeyClickActionWithMods::new_with_mods(Key::from_str(&EventType::EV_KEY, "KEY_8").unwrap(), KeyModifierFlags::new().tap_mut(|v| v.shift()))
+        )));
     }
 }
\ No newline at end of file
@@ -80,6 +80,212 @@ pub(super) fn key_mapping(input: &str) -> ResNew<&str, Expr> {
     })
 }
 
+pub(super) fn key_mapping_tap_hold(input: &str) -> ResNew<&str, Expr> {
+    tuple((
+        key_action_with_flags,
+        tag_custom("::"),
+        ws0,
+        tag_custom("tap_hold"),
+        ws0,
+        tag_custom("("),
+        ws0,
+        key_action_with_flags,
+        ws0,
+        tag_custom(","),
+        ws0,
+        key_action_with_flags,
+        ws0,
+        opt(tuple((tag_custom(","), ws0, number, ws0))),
+        tag_custom(")"),
+    ))(input).and_then(|(next, v)| {
+        let (from, tap, hold) = match (v.0.0, v.7.0, v.11.0) {
+            (
+                ParsedKeyAction::KeyClickAction(from),
+                ParsedKeyAction::KeyClickAction(tap),
+                ParsedKeyAction::KeyClickAction(hold),
+            ) if from.modifiers == KeyModifierFlags::new() => (from, tap, hold),
+            _ => return Err(make_generic_nom_err_options(input, vec!["tap-hold mapping".to_string()])),
+        };
+
+        let tapping_term = match v.13 {
+            Some((_, _, (Expr::Value(ValueType::Number(millis)), _), _)) => time::Duration::from_millis(millis as u64),
+            _ => DEFAULT_TAPPING_TERM,
+        };
+
+        Ok((next, (Expr::TapHoldMapping(from.key, TapHold { tap, hold, tapping_term }), None)))
+    })
+}
+
+pub(super) fn key_actions_to_block(actions: Vec<KeyAction>) -> Block {
+    Block::new().tap_mut(|b| b.statements = actions
+        .into_iter()
+        .map(|v| Stmt::Expr(Expr::KeyAction(v)))
+        .collect())
+}
+
+pub(super) fn key_mapping_chord(input: &str) -> ResNew<&str, Expr> {
+    tuple((
+        key,
+        many1(tuple((ws0, tag_custom("+"), ws0, key))),
+        tag_custom("::"),
+        ws0,
+        alt((
+            map(block, |(block, last_err)| ((block, Block::new()), last_err)),
+            map(key_sequence, |(seq, last_err)| ((key_actions_to_block(seq.to_key_actions()), Block::new()), last_err)),
+            map(key_action_with_flags, |(action, last_err)| {
+                match action {
+                    // clicks are held down for as long as the chord is held down
+                    ParsedKeyAction::KeyClickAction(to) => {
+                        ((key_actions_to_block(to.down_actions()), key_actions_to_block(to.up_actions())), last_err)
+                    }
+                    action => ((key_actions_to_block(vec![action].to_key_actions()), Block::new()), last_err),
+                }
+            }),
+        )),
+    ))(input).and_then(|(next, v)| {
+        let mut keys = vec![v.0.0];
+        keys.extend(v.1.into_iter().map(|v| v.3.0));
+
+        // chord keys are physical keys, modifier flags and duplicates make no sense here
+        let mut chord = vec![];
+        for (key, flags) in keys {
+            if flags != KeyModifierFlags::new() || chord.contains(&key) {
+                return Err(make_generic_nom_err_options(input, vec!["chord".to_string()]));
+            }
+            chord.push(key);
+        }
+
+        let ((down, up), last_err) = v.4;
+        Ok((next, (Expr::ChordMapping(chord, down, up), last_err)))
+    })
+}
+
+/// `a::"hello ${name}"`, the text is only known at runtime so it gets typed through `send`.
+/// Interpolated values are typed as they are, braces in them don't start key actions.
+pub(super) fn key_mapping_interpolated(input: &str) -> ResNew<&str, Expr> {
+    tuple((
+        key_action_with_flags,
+        tag_custom("::"),
+        string_literal,
+    ))(input).and_then(|(next, v)| {
+        let parts = v.2.0;
+        if !parts.iter().any(|part| matches!(part, StringPart::Interpolation(_))) {
+            return Err(make_generic_nom_err_new(input));
+        }
+        let text = string_parts_expr(parts.into_iter().map(|part| match part {
+            StringPart::Interpolation(expr) =>
+                StringPart::Interpolation(Expr::FunctionCall("escape_key_sequence".to_string(), vec![expr])),
+            part => part,
+        }).collect());
+        let block = Block::new()
+            .tap_mut(|b| { b.push_expr(Expr::FunctionCall("send".to_string(), vec![text])); });
+
+        let expr = match v.0.0 {
+            ParsedKeyAction::KeyAction(from) => Expr::map_key_block(from, block),
+            ParsedKeyAction::KeyClickAction(from) => Expr::map_key_click_block(from, block),
+        };
+        Ok((next, (expr, None)))
+    })
+}
+
+fn sequence_step(input: &str) -> ResNew<&str, SequenceStep> {
+    alt((
+        map(tag_custom("<leader>"), |_| (SequenceStep::Leader, None)),
+        |input| key_action_with_flags(input).and_then(|(next, (action, last_err))| match action {
+            ParsedKeyAction::KeyClickAction(action) => Ok((next, (SequenceStep::Key(action), last_err))),
+            _ => Err(make_generic_nom_err_options(input, vec!["key".to_string()])),
+        }),
+    ))(input)
+}
+
+fn key_mapping_any(input: &str) -> ResNew<&str, Expr> {
+    alt((key_mapping, key_mapping_interpolated, key_mapping_inline))(input)
+}
+
+/// `repeat(pass)`, `repeat(none)` or `repeat(delay, interval)` after a mapping to repeat with our
+/// own timing in milliseconds, only click mappings repeat. An invalid interval is `None`.
+fn key_mapping_repeat(input: &str) -> ResNew<&str, Option<KeyRepeat>> {
+    tuple((
+        ws1,
+        tag_custom("repeat"),
+        ws0,
+        tag_custom("("),
+        ws0,
+        alt((
+            map(tag_custom("pass"), |_| Some(KeyRepeat::Pass)),
+            map(tag_custom("none"), |_| Some(KeyRepeat::Suppress)),
+            map(tuple((number, ws0, tag_custom(","), ws0, number)), |v| match (v.0.0, v.4.0) {
+                (Expr::Value(ValueType::Number(delay)), Expr::Value(ValueType::Number(interval))) if interval >= 1.0 =>
+                    Some(KeyRepeat::Software(SoftwareRepeat {
+                        delay: time::Duration::from_millis(delay as u64),
+                        interval: time::Duration::from_millis(interval as u64),
+                    })),
+                _ => None,
+            }),
+        )),
+        ws0,
+        tag_custom(")"),
+    ))(input).map(|(next, v)| (next, (v.5, None)))
+}
+
+/// `if <expr>` after a mapping, the mapping only applies while the expression is true.
+fn key_mapping_guard(input: &str) -> ResNew<&str, Expr> {
+    tuple((ws1, tag_custom("if"), ws1, expr))(input)
+        .map(|(next, v)| (next, v.3))
+}
+
+/// A key mapping followed by its optional suffixes, the mapping itself is only parsed once.
+pub(super) fn key_mapping_suffixed(input: &str) -> ResNew<&str, Expr> {
+    let (next, (mut mapping, last_err)) = key_mapping_any(input)?;
+
+    let (next, repeat) = match key_mapping_repeat(next) {
+        Ok((next, (repeat, _))) => {
+            let repeat = repeat.ok_or_else(|| make_generic_nom_err_options(input, vec!["repeat interval".to_string()]))?;
+            mapping = mapping.with_key_repeat(&repeat)
+                .ok_or_else(|| make_generic_nom_err_options(input, vec!["click mapping".to_string()]))?;
+            (next, Some(repeat))
+        }
+        Err(_) => (next, None),
+    };
+
+    let (next, last_err) = match key_mapping_guard(next) {
+        Ok((next, (guard, last_err))) => {
+            mapping = match mapping {
+                Expr::KeyMapping(mappings) => Expr::GuardedKeyMapping(mappings, Box::new(guard)),
+                _ => unreachable!(),
+            };
+            (next, last_err)
+        }
+        Err(_) => (next, last_err),
+    };
+
+    let expr = match repeat {
+        Some(KeyRepeat::Software(repeat)) => Expr::RepeatKeyMapping(Box::new(mapping), repeat),
+        _ => mapping,
+    };
+    Ok((next, (expr, last_err)))
+}
+
+pub(super) fn key_mapping_sequence(input: &str) -> ResNew<&str, Expr> {
+    tuple((
+        sequence_step,
+        many1(tuple((ws1, sequence_step))),
+        tag_custom("::"),
+        ws0,
+        alt((
+            block,
+            map(key_sequence, |(seq, last_err)| (key_actions_to_block(seq.to_key_actions()), last_err)),
+            map(key_action_with_flags, |(action, last_err)| (key_actions_to_block(vec![action].to_key_actions()), last_err)),
+        )),
+    ))(input).map(|(next, v)| {
+        let mut steps = vec![v.0.0];
+        steps.extend(v.1.into_iter().map(|v| v.1.0));
+
+        let (block, last_err) = v.4;
+        (next, (Expr::SequenceMapping(steps, block), last_err))
+    })
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -126,6 +332,137 @@ mod tests {
         ])));
     }
 
+    #[test]
+    fn test_key_mapping_interpolated() {
+        assert_eq!(key_mapping_interpolated("a::\"${name}\""), nom_ok(Expr::map_key_click_block(
+            KeyClickActionWithMods::new(*KEY_A),
+            Block::new().tap_mut(|b| { b.push_expr(Expr::FunctionCall("send".to_string(), vec![Expr::Add(
+                Box::new(Expr::Value(ValueType::String("".to_string()))),
+                Box::new(Expr::FunctionCall("escape_key_sequence".to_string(), vec![Expr::Name("name".to_string())])),
+            )])); }),
+        )));
+        assert!(matches!(key_mapping_interpolated("a::\"b\""), Err(..)));
+    }
+
+    #[test]
+    fn test_key_mapping_tap_hold() {
+        assert_eq!(key_mapping_tap_hold("capslock::tap_hold(esc, ctrl)"), nom_ok(Expr::TapHoldMapping(
+            *KEY_CAPSLOCK,
+            TapHold {
+                tap: KeyClickActionWithMods::new(*KEY_ESC),
+                hold: KeyClickActionWithMods::new(*KEY_LEFT_CTRL),
+                tapping_term: DEFAULT_TAPPING_TERM,
+            },
+        )));
+
+        assert_eq!(key_mapping_tap_hold("a::tap_hold(a, ^a, 150)"), nom_ok(Expr::TapHoldMapping(
+            *KEY_A,
+            TapHold {
+                tap: KeyClickActionWithMods::new(*KEY_A),
+                hold: KeyClickActionWithMods::new(*KEY_A).tap_mut(|v| v.modifiers.ctrl()),
+                tapping_term: time::Duration::from_millis(150),
+            },
+        )));
+
+        assert!(matches!(key_mapping_tap_hold("^a::tap_hold(a, b)"), Err(..)));
+        assert!(matches!(key_mapping_tap_hold("a::tap_hold({a down}, b)"), Err(..)));
+    }
+
+    #[test]
+    fn test_key_mapping_chord() {
+        assert_eq!(key_mapping_chord("j+k::esc"), nom_ok(Expr::ChordMapping(
+            vec![*KEY_J, *KEY_K],
+            key_actions_to_block(vec![KeyAction::new(*KEY_ESC, TYPE_DOWN)]),
+            key_actions_to_block(vec![KeyAction::new(*KEY_ESC, TYPE_UP)]),
+        )));
+
+        assert_eq!(nom_no_last_err(key_mapping_chord("j + k + l::{ print(\"hi\"); }")), nom_ok(Expr::ChordMapping(
+            vec![*KEY_J, *KEY_K, *KEY_L],
+            nom_eval(block("{ print(\"hi\"); }")),
+            Block::new(),
+        )));
+
+        assert!(matches!(key_mapping_chord("j::esc"), Err(..)));
+        assert!(matches!(key_mapping_chord("j+j::esc"), Err(..)));
+        assert!(matches!(key_mapping_chord("J+k::esc"), Err(..)));
+    }
+
+    #[test]
+    fn test_key_mapping_suffixed() {
+        let mappings = match nom_eval(key_mapping_inline("a::b")) {
+            Expr::KeyMapping(mappings) => mappings,
+            _ => unreachable!(),
+        };
+        assert_eq!(nom_no_last_err(key_mapping_suffixed("a::b if active_window_class() == \"firefox\"")), nom_ok(Expr::GuardedKeyMapping(
+            mappings.clone(),
+            Box::new(nom_eval(expr("active_window_class() == \"firefox\""))),
+        )));
+
+        assert!(matches!(nom_eval(key_mapping_suffixed("a::{} if (x)")), Expr::GuardedKeyMapping(..)));
+        assert_eq!(key_mapping_suffixed("a::b"), nom_ok(Expr::KeyMapping(mappings.clone())));
+        assert_eq!(key_mapping_suffixed("a::b ifx").map(|(next, _)| next), Ok(" ifx"));
+
+        assert!(matches!(nom_eval(key_mapping_suffixed("a::b repeat(200, 30) if x")),
+            Expr::RepeatKeyMapping(mapping, _) if matches!(*mapping, Expr::GuardedKeyMapping(..))));
+    }
+
+    #[test]
+    fn test_key_mapping_repeat() {
+        let repeat_block = |expr: Expr| match expr {
+            Expr::KeyMapping(mappings) => mappings.into_iter().find(|v| v.from.value == TYPE_REPEAT).unwrap().to,
+            _ => unreachable!(),
+        };
+        let down_block = match nom_eval(key_mapping_inline("a::\"ab\"")) {
+            Expr::KeyMapping(mappings) => mappings[0].to.clone(),
+            _ => unreachable!(),
+        };
+
+        // sequences and blocks run again on every repeat
+        assert_eq!(repeat_block(nom_eval(key_mapping_suffixed("a::\"ab\" repeat(pass)"))), down_block);
+        assert_eq!(repeat_block(nom_eval(key_mapping_suffixed("a::b repeat( none )"))), Block::new());
+        // clicks keep passing the repeat on
+        assert_eq!(key_mapping_suffixed("a::b repeat(pass)"), nom_ok(Expr::map_key_click(
+            &KeyClickActionWithMods::new(*KEY_A),
+            &KeyClickActionWithMods::new(*KEY_B),
+        )));
+
+        assert_eq!(nom_eval(key_mapping_suffixed("a::{} repeat(250, 30)")), Expr::RepeatKeyMapping(
+            Box::new(Expr::map_key_click_block(KeyClickActionWithMods::new(*KEY_A), Block::new())
+                .with_key_repeat(&KeyRepeat::Pass).unwrap()),
+            SoftwareRepeat { delay: time::Duration::from_millis(250), interval: time::Duration::from_millis(30) },
+        ));
+
+        assert!(matches!(key_mapping_suffixed("{a down}::b repeat(none)"), Err(..)));
+        assert!(matches!(key_mapping_suffixed("a::b repeat(250, 0)"), Err(..)));
+        assert_eq!(key_mapping_suffixed("a::b repeat(always)").map(|(next, _)| next), Ok(" repeat(always)"));
+    }
+
+    #[test]
+    fn test_nested_key_mappings() {
+        // every mapping is parsed once, so nesting doesn't multiply the parsing work
+        let nested = (0..10).fold("a::b".to_string(), |inner, _| format!("a::{{ {}; }} repeat(pass) if x", inner));
+        assert!(matches!(nom_eval(key_mapping_suffixed(&nested)), Expr::GuardedKeyMapping(..)));
+    }
+
+    #[test]
+    fn test_key_mapping_sequence() {
+        assert_eq!(key_mapping_sequence("<leader> g s::esc"), nom_ok(Expr::SequenceMapping(
+            vec![SequenceStep::Leader, SequenceStep::Key(KeyClickActionWithMods::new(*KEY_G)), SequenceStep::Key(KeyClickActionWithMods::new(*KEY_S))],
+            key_actions_to_block(vec![KeyAction::new(*KEY_ESC, TYPE_DOWN), KeyAction::new(*KEY_ESC, TYPE_UP)]),
+        )));
+
+        assert_eq!(nom_no_last_err(key_mapping_sequence("g ^t::{ print(\"hi\"); }")), nom_ok(Expr::SequenceMapping(
+            vec![
+                SequenceStep::Key(KeyClickActionWithMods::new(*KEY_G)),
+                SequenceStep::Key(KeyClickActionWithMods::new_with_mods(*KEY_T, KeyModifierFlags::new().tap_mut(|v| v.ctrl()))),
+            ],
+            nom_eval(block("{ print(\"hi\"); }")),
+        )));
+
+        assert!(matches!(key_mapping_sequence("g::esc"), Err(..)));
+        assert!(matches!(key_mapping_sequence("g {s down}::esc"), Err(..)));
+    }
+
     #[test]
     fn test_key_mapping_complex() {
         // TODO add when implemented
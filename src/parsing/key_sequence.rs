@@ -4,32 +4,29 @@ use nom::multi::many1;
 
 use super::*;
 
-pub(super) fn key_sequence(input: &str) -> ResNew<&str, Vec<ParsedKeyAction>> {
-    tuple((
-        tag_custom("\""),
-        many1(
-            alt((
-                map_res(
-                    recognize(tuple((
-                                        tag_custom("{"),
-                                        terminated(take_until("}"), tag_custom("}"))),
-                    )),
-                    |input| {
-                        let (input, action) = key_action(input)?;
-                        // TODO properly propagate child error
-                        if !input.is_empty() {
-                            return Err(make_generic_nom_err_new(input));
-                        }
+/// The unquoted contents of a key sequence, e.g. `hello{enter}`.
+pub(super) fn key_sequence_body(input: &str) -> ResNew<&str, Vec<ParsedKeyAction>> {
+    many1(
+        alt((
+            map_res(
+                recognize(tuple((
+                                tag_custom("{"),
+                                terminated(take_until("}"), tag_custom("}"))),
+                )),
+                |input| {
+                    let (input, action) = key_action(input)?;
+                    // TODO properly propagate child error
+                    if !input.is_empty() {
+                        return Err(make_generic_nom_err_new(input));
+                    }
 
-                        Ok((input, action))
-                    },
-                ),
-                map_res(take(1usize), key_action),
-            )),
-        ),
-        tag_custom("\""),
-    ))(input).and_then(|(next, val)| {
-        let seq = val.1.into_iter()
+                    Ok((input, action))
+                },
+            ),
+            map_res(take(1usize), key_action),
+        )),
+    )(input).and_then(|(next, val)| {
+        let seq = val.into_iter()
             .map(|v| {
                 if !v.0.is_empty() { return Err(make_generic_nom_err_new(input)); }
                 Ok(v.1.0)
@@ -42,6 +39,21 @@ pub(super) fn key_sequence(input: &str) -> ResNew<&str, Vec<ParsedKeyAction>> {
     })
 }
 
+pub(super) fn key_sequence(input: &str) -> ResNew<&str, Vec<ParsedKeyAction>> {
+    let (next, (parts, _)) = string_literal(input)?;
+
+    // interpolated sequences can only be typed at runtime
+    let text = match parts.as_slice() {
+        [StringPart::Text(text)] => text,
+        _ => return Err(make_generic_nom_err_options(input, vec!["key sequence".to_string()])),
+    };
+
+    match key_sequence_body(text) {
+        Ok(("", (seq, _))) => Ok((next, (seq, None))),
+        _ => Err(make_generic_nom_err_options(input, vec!["key sequence".to_string()])),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -74,4 +86,26 @@ mod tests {
             ParsedKeyAction::KeyAction(KeyActionWithMods::new(*KEY_LEFT_SHIFT, TYPE_UP, KeyModifierFlags::new())),
         ]));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_key_sequence_escapes() {
+        assert_eq!(key_sequence(r#""\"\n""#), nom_ok(vec![
+            ParsedKeyAction::KeyClickAction(KeyClickActionWithMods {
+                key: Key::from_str(&EventType::EV_KEY, "KEY_APOSTROPHE").unwrap(),
+                modifiers: KeyModifierFlags::new().tap_mut(|f| { f.shift(); }),
+            }),
+            ParsedKeyAction::KeyClickAction(KeyClickActionWithMods { key: *KEY_ENTER, modifiers: KeyModifierFlags::new() }),
+        ]));
+        assert_eq!(key_sequence(r#"r"\""#), nom_ok(vec![
+            ParsedKeyAction::KeyClickAction(KeyClickActionWithMods { key: *KEY_BACKSLASH, modifiers: KeyModifierFlags::new() }),
+        ]));
+        assert_eq!(key_sequence(r#""\r{{}""#), nom_ok(vec![
+            ParsedKeyAction::KeyClickAction(KeyClickActionWithMods { key: *KEY_ENTER, modifiers: KeyModifierFlags::new() }),
+            ParsedKeyAction::KeyClickAction(KeyClickActionWithMods {
+                key: Key::from_str(&EventType::EV_KEY, "KEY_LEFTBRACE").unwrap(),
+                modifiers: KeyModifierFlags::new().tap_mut(|f| { f.shift(); }),
+            }),
+        ]));
+        assert!(matches!(key_sequence("\"${a}\""), Err(..)));
+    }
+}
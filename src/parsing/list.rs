@@ -0,0 +1,66 @@
+use super::*;
+
+pub(super) fn list(input: &str) -> ResNew<&str, Expr> {
+    tuple((
+        tag_custom("["),
+        ws0,
+        opt(tuple((
+            expr,
+            many0(tuple((ws0, tag_custom(","), ws0, expr))),
+            ws0,
+            opt(tag_custom(",")),
+        ))),
+        ws0,
+        tag_custom("]"),
+    ))(input)
+        .map_err(|_: NomErr<CustomError<_>>| make_generic_nom_err_options(input, vec!["list".to_string()]))
+        .map(|(next, v)| {
+            let items = match v.2 {
+                Some((first, rest, _, _)) => {
+                    let mut items = vec![first.0];
+                    items.extend(rest.into_iter().map(|v| v.3.0));
+                    items
+                }
+                None => vec![],
+            };
+            (next, (Expr::List(items), None))
+        })
+}
+
+pub(super) fn index(input: &str) -> ResNew<&str, Expr> {
+    tuple((tag_custom("["), ws0, expr, ws0, tag_custom("]")))(input)
+        .map(|(next, v)| (next, (v.2.0, None)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list() {
+        assert_eq!(list("[]"), nom_ok(Expr::List(vec![])));
+        assert_eq!(list("[1, \"a\", [true]]"), nom_ok(Expr::List(vec![
+            Expr::Value(ValueType::Number(1.0)),
+            Expr::Value(ValueType::String("a".to_string())),
+            Expr::List(vec![Expr::Value(ValueType::Bool(true))]),
+        ])));
+        assert_eq!(list("[ 1,\n  2,\n]"), nom_ok(Expr::List(vec![
+            Expr::Value(ValueType::Number(1.0)),
+            Expr::Value(ValueType::Number(2.0)),
+        ])));
+
+        assert!(matches!(list("[1 2]"), Err(..)));
+    }
+
+    #[test]
+    fn test_index() {
+        assert_eq!(nom_no_last_err(expr("foo[1][i + 1]")), nom_ok(Expr::Index(
+            Box::new(Expr::Index(
+                Box::new(Expr::Name("foo".to_string())),
+                Box::new(Expr::Value(ValueType::Number(1.0))),
+            )),
+            Box::new(nom_eval(expr("i + 1"))),
+        )));
+    }
+}
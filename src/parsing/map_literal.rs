@@ -0,0 +1,51 @@
+use super::*;
+
+fn map_entry(input: &str) -> ResNew<&str, (String, Expr)> {
+    tuple((string_value, ws0, tag_custom(":"), ws0, expr))(input)
+        .map(|(next, v)| (next, ((v.0.0, v.4.0), None)))
+}
+
+pub(super) fn map_literal(input: &str) -> ResNew<&str, Expr> {
+    tuple((
+        tag_custom("{"),
+        ws0,
+        opt(tuple((
+            map_entry,
+            many0(tuple((ws0, tag_custom(","), ws0, map_entry))),
+            ws0,
+            opt(tag_custom(",")),
+        ))),
+        ws0,
+        tag_custom("}"),
+    ))(input)
+        .map_err(|_: NomErr<CustomError<_>>| make_generic_nom_err_options(input, vec!["map".to_string()]))
+        .map(|(next, v)| {
+            let items = match v.2 {
+                Some((first, rest, _, _)) => {
+                    let mut items = vec![first.0];
+                    items.extend(rest.into_iter().map(|v| v.3.0));
+                    items
+                }
+                None => vec![],
+            };
+            (next, (Expr::Map(items), None))
+        })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_literal() {
+        assert_eq!(map_literal("{}"), nom_ok(Expr::Map(vec![])));
+        assert_eq!(map_literal("{ \"firefox\": \"^t\",\n  \"kitty\": [1],\n}"), nom_ok(Expr::Map(vec![
+            ("firefox".to_string(), Expr::Value(ValueType::String("^t".to_string()))),
+            ("kitty".to_string(), Expr::List(vec![Expr::Value(ValueType::Number(1.0))])),
+        ])));
+
+        assert!(matches!(map_literal("{a down}"), Err(..)));
+        assert!(matches!(map_literal("{ \"a\" 1 }"), Err(..)));
+    }
+}
@@ -7,10 +7,11 @@ use nom::character::complete::*;
 use nom::combinator::{map, opt};
 use nom::error::{ParseError};
 use nom::IResult;
-use nom::multi::many0;
+use nom::multi::{many0, many1};
 use nom::sequence::*;
 use tap::Tap;
 
+use break_statement::*;
 use continue_statement::*;
 use custom_combinators::*;
 use error::*;
@@ -19,35 +20,52 @@ use for_loop::*;
 use function::*;
 use identifier::*;
 use if_statement::*;
+use import::*;
 use key::*;
 use key_action::*;
 use key_mapping::*;
 use key_sequence::*;
 use lambda::*;
+use list::*;
+use map_literal::*;
 use primitives::*;
+use rel_mapping::*;
 use return_statement::*;
+use source_location::*;
+use string_literal::*;
 #[cfg(test)]
 use tests::*;
 use variable::*;
+use when_window::*;
+use while_loop::*;
 
 use crate::*;
 
 pub mod parser;
 mod return_statement;
 mod continue_statement;
+mod break_statement;
 mod custom_combinators;
 mod expression;
 mod function;
 mod identifier;
 mod if_statement;
+mod import;
 mod key;
 mod key_action;
 mod key_mapping;
 mod key_sequence;
 mod lambda;
+mod list;
+mod map_literal;
 mod primitives;
+mod rel_mapping;
+mod source_location;
+mod string_literal;
 mod variable;
 mod for_loop;
+mod while_loop;
+mod when_window;
 mod error;
 
 
@@ -55,8 +73,14 @@ fn stmt(input: &str) -> ResNew<&str, Stmt> {
     alt((
         return_statement,
         continue_statement,
+        break_statement,
         if_stmt,
         for_loop,
+        for_in_loop,
+        while_loop,
+        when_window_stmt,
+        import_stmt,
+        function_declaration,
         map(
             tuple((expr, tag_custom(";"))),
             |(v, _)| (Stmt::Expr(v.0), v.1),
@@ -66,24 +90,26 @@ fn stmt(input: &str) -> ResNew<&str, Stmt> {
 }
 
 fn block_body(input: &str) -> ResNew<&str, Block> {
-    let res = stmt(input);
+    let res = located(stmt)(input);
 
-    let (input, (first_stmt, initial_last_err)) = match res {
+    let (input, ((first_span, first_stmt), initial_last_err)) = match res {
         Ok(v) => v,
         Err(NomErr::Error(last_err)) => return Ok((input, (Block::new(), Some(last_err)))),
         Err(_) => return Ok((input, (Block::new(), None))),
     };
 
-    let (input, (pairs, mut last_err)) = many0_err(tuple((ws0, stmt)))(input)?;
+    let (input, (pairs, mut last_err)) = many0_err(tuple((ws0, located(stmt))))(input)?;
 
     if let Some(err) = initial_last_err {
         last_err = last_err.or(err);
     }
 
     let block = Block::new().tap_mut(|b| {
-        let mut statements: Vec<Stmt> = pairs.into_iter().map(|x| x.1.0).collect();
-        statements.insert(0, first_stmt);
+        let (spans, statements): (Vec<Span>, Vec<Stmt>) = std::iter::once((first_span, first_stmt))
+            .chain(pairs.into_iter().map(|x| x.1.0))
+            .unzip();
         b.statements = statements;
+        b.spans = spans;
     });
 
     Ok((input, (block, Some(last_err))))
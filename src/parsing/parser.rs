@@ -1,8 +1,8 @@
 
 use super::*;
 
-pub(crate) fn parse_script<>(raw_script: &str) -> Result<Block> {
-    match global_block(raw_script) {
+pub(crate) fn parse_script<>(raw_script: &str, file_name: &str) -> Result<Block> {
+    match with_source_file(file_name, raw_script, || global_block(raw_script)) {
         Ok((v, (block, last_err))) => {
             if v.is_empty() {
                 Ok(block)
@@ -30,9 +30,7 @@ pub(crate) fn parse_script<>(raw_script: &str) -> Result<Block> {
 }
 
 pub(crate) fn parse_key_sequence(raw: &str) -> Result<Vec<KeyAction>> {
-    // TODO remove this workaround (allow seq to be parsed without quotes)
-    let raw = format!("\"{}\"", raw);
-    match key_sequence(&raw) {
+    match key_sequence_body(raw) {
         Ok(v) => {
             if v.0.is_empty() {
                 Ok(v.1.0.to_key_actions())
@@ -44,8 +42,34 @@ pub(crate) fn parse_key_sequence(raw: &str) -> Result<Vec<KeyAction>> {
     }
 }
 
+/// The `{...}` groups of a key sequence that aren't valid key actions, they get typed out
+/// character by character instead.
+pub(crate) fn invalid_key_actions(raw: &str) -> Vec<String> {
+    let mut invalid = vec![];
+    let mut rest = raw;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end + 1,
+            None => break,
+        };
+        let group = &rest[start..end];
+        if !matches!(key_action(group), Ok(("", _))) {
+            invalid.push(group.to_string());
+        }
+        rest = &rest[end..];
+    }
+    invalid
+}
+
+pub(crate) fn parse_key_click_action_with_mods(raw: &str) -> Result<KeyClickActionWithMods> {
+    match key_action_with_flags(raw) {
+        Ok(("", (ParsedKeyAction::KeyClickAction(action), _))) => Ok(action),
+        _ => Err(anyhow!("failed to parse key '{}'", raw)),
+    }
+}
+
 pub(crate) fn parse_key_action_with_mods(from: &str, to: Block) -> Result<Expr> {
-    let from = key_action_with_flags(from).expect("failed to parse mapping trigger");
+    let from = key_action_with_flags(from).map_err(|_| anyhow!("failed to parse mapping trigger '{}'", from))?;
     if !from.0.is_empty() { return Err(anyhow!("failed to parse mapping trigger")); }
     let from = from.1;
 
@@ -67,5 +91,9 @@ mod tests {
         assert_eq!(parse_key_sequence("hello{enter}world").unwrap(),
                    nom_eval(key_sequence("\"hello{enter}world\"")).to_key_actions(),
         );
+        assert_eq!(invalid_key_actions("a{enter}{entr}{shift down}{{}"), vec!["{entr}".to_string()]);
+        assert_eq!(parse_key_sequence("say \"hi\"").unwrap(),
+                   nom_eval(key_sequence(r#""say \"hi\"""#)).to_key_actions(),
+        );
     }
 }
\ No newline at end of file
@@ -3,11 +3,37 @@ use nom::number::complete::double;
 use super::*;
 
 pub(super) fn string(input: &str) -> ResNew<&str, Expr> {
-    tuple((tag_custom("\""), take_until("\""), tag_custom("\"")))(input)
-        .map_err(|_: NomErr<CustomError<_>>| make_generic_nom_err_options(input, vec!["string".to_string()]))
-        .map(|(next, v)|
-            (next, (Expr::Value(ValueType::String(v.1.to_string())), None))
-        )
+    string_literal(input)
+        .map_err(|err| match err {
+            NomErr::Error(err) if err.input == input => make_generic_nom_err_options(input, vec!["string".to_string()]),
+            err => err,
+        })
+        .map(|(next, (parts, last_err))| (next, (string_parts_expr(parts), last_err)))
+}
+
+/// Interpolated strings are desugared into concatenations.
+pub(super) fn string_parts_expr(parts: Vec<StringPart>) -> Expr {
+    let mut parts = parts.into_iter().map(|part| match part {
+        StringPart::Text(text) => Expr::Value(ValueType::String(text)),
+        StringPart::Interpolation(expr) => expr,
+    });
+    let mut expr = match parts.next() {
+        Some(expr @ Expr::Value(ValueType::String(_))) => expr,
+        Some(expr) => Expr::Add(Box::new(Expr::Value(ValueType::String("".to_string()))), Box::new(expr)),
+        None => unreachable!(),
+    };
+    for part in parts {
+        expr = Expr::Add(Box::new(expr), Box::new(part));
+    }
+    expr
+}
+
+/// A string literal without interpolation.
+pub(super) fn string_value(input: &str) -> ResNew<&str, String> {
+    string(input).and_then(|(next, (v, last_err))| match v {
+        Expr::Value(ValueType::String(v)) => Ok((next, (v, last_err))),
+        _ => Err(make_generic_nom_err_options(input, vec!["string without interpolation".to_string()])),
+    })
 }
 
 pub(super) fn boolean(input: &str) -> ResNew<&str, Expr> {
@@ -46,6 +72,17 @@ mod tests {
         assert!(matches!(boolean("foo"), Err(..)));
 
         assert_eq!(string("\"hello world\""), nom_ok(Expr::Value(ValueType::String("hello world".to_string()))));
+        assert_eq!(string("\"a${1}b\""), nom_ok(Expr::Add(
+            Box::new(Expr::Add(
+                Box::new(Expr::Value(ValueType::String("a".to_string()))),
+                Box::new(Expr::Value(ValueType::Number(1.0))),
+            )),
+            Box::new(Expr::Value(ValueType::String("b".to_string()))),
+        )));
+        assert_eq!(string("\"${1}\""), nom_ok(Expr::Add(
+            Box::new(Expr::Value(ValueType::String("".to_string()))),
+            Box::new(Expr::Value(ValueType::Number(1.0))),
+        )));
     }
 
     #[test]
@@ -0,0 +1,104 @@
+use std::cmp::Ordering;
+
+use evdev_rs::enums::EV_REL;
+
+use super::*;
+
+fn rel_axis(input: &str) -> ResNew<&str, EventCode> {
+    let (next, (name, _)) = ident(input)?;
+    if !name.starts_with("rel_") { return Err(make_generic_nom_err_options(input, vec!["relative axis".to_string()])); }
+
+    match EventCode::from_str(&EventType::EV_REL, &name.to_uppercase()) {
+        Some(axis) => Ok((next, (axis, None))),
+        None => Err(make_generic_nom_err_options(input, vec!["relative axis".to_string()])),
+    }
+}
+
+/// `wheel_up`, `wheel_down`, `wheel_left`, `wheel_right` or a comparison like `rel_x > 0`.
+fn rel_trigger(input: &str) -> ResNew<&str, RelTrigger> {
+    alt((
+        map(tag_custom("wheel_up"), |_| (RelTrigger::new(EV_REL::REL_WHEEL, Ordering::Greater, 0), None)),
+        map(tag_custom("wheel_down"), |_| (RelTrigger::new(EV_REL::REL_WHEEL, Ordering::Less, 0), None)),
+        map(tag_custom("wheel_left"), |_| (RelTrigger::new(EV_REL::REL_HWHEEL, Ordering::Less, 0), None)),
+        map(tag_custom("wheel_right"), |_| (RelTrigger::new(EV_REL::REL_HWHEEL, Ordering::Greater, 0), None)),
+        |input| tuple((
+            rel_axis,
+            ws0,
+            alt((tag_custom(">="), tag_custom("<="), tag_custom("=="), tag_custom(">"), tag_custom("<"))),
+            ws0,
+            number,
+        ))(input).and_then(|(next, v)| {
+            let out_of_range = || make_generic_nom_err_options(input, vec!["whole number".to_string()]);
+            let threshold = match v.4.0 {
+                Expr::Value(ValueType::Number(n)) if n.fract() == 0.0 && n >= i32::MIN as f64 && n <= i32::MAX as f64 => n as i32,
+                _ => return Err(out_of_range()),
+            };
+            // event values are whole numbers, so '>= n' is the same as '> n - 1'
+            let (ordering, threshold) = match v.2 {
+                ">=" => (Ordering::Greater, threshold.checked_sub(1)),
+                "<=" => (Ordering::Less, threshold.checked_add(1)),
+                "==" => (Ordering::Equal, Some(threshold)),
+                ">" => (Ordering::Greater, Some(threshold)),
+                _ => (Ordering::Less, Some(threshold)),
+            };
+            let threshold = threshold.ok_or_else(out_of_range)?;
+            Ok((next, (RelTrigger { axis: v.0.0, ordering, threshold }, None)))
+        }),
+    ))(input)
+}
+
+/// `{wheel_up}::volumeup`, the mapping runs for relative events of mice and wheels instead of keys.
+pub(super) fn rel_mapping(input: &str) -> ResNew<&str, Expr> {
+    tuple((
+        tag_custom("{"),
+        ws0,
+        rel_trigger,
+        ws0,
+        tag_custom("}"),
+        tag_custom("::"),
+        ws0,
+        alt((
+            block,
+            map(key_sequence, |(seq, last_err)| (key_actions_to_block(seq.to_key_actions()), last_err)),
+            map(key_action_with_flags, |(action, last_err)| (key_actions_to_block(vec![action].to_key_actions()), last_err)),
+        )),
+        opt(tuple((ws1, tag_custom("if"), ws1, expr))),
+    ))(input).map(|(next, v)| {
+        let (block, mut last_err) = v.7;
+        let guard = v.8.map(|(_, _, _, (guard, guard_last_err))| {
+            last_err = guard_last_err;
+            Box::new(guard)
+        });
+        (next, (Expr::RelMapping(v.2.0, guard, block), last_err))
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rel_mapping() {
+        let volume_up = Key::from_str(&EventType::EV_KEY, "KEY_VOLUMEUP").unwrap();
+        assert_eq!(rel_mapping("{wheel_up}::volumeup"), nom_ok(Expr::RelMapping(
+            RelTrigger::new(EV_REL::REL_WHEEL, Ordering::Greater, 0),
+            None,
+            key_actions_to_block(vec![KeyAction::new(volume_up, TYPE_DOWN), KeyAction::new(volume_up, TYPE_UP)]),
+        )));
+
+        assert_eq!(nom_eval(rel_mapping("{ rel_x >= 5 }::{}")), Expr::RelMapping(
+            RelTrigger::new(EV_REL::REL_X, Ordering::Greater, 4),
+            None,
+            Block::new(),
+        ));
+
+        assert!(matches!(nom_eval(rel_mapping("{wheel_left}::{} if x")), Expr::RelMapping(_, Some(_), _)));
+        assert!(matches!(rel_mapping("{rel_x > 0.5}::{}"), Err(..)));
+        assert!(matches!(rel_mapping("{rel_x <= 3000000000}::{}"), Err(..)));
+        assert!(matches!(rel_mapping("{rel_x <= 2147483647}::{}"), Err(..)));
+        assert!(matches!(rel_mapping("{rel_x >= -2147483648}::{}"), Err(..)));
+        assert!(matches!(rel_mapping("{rel_foo > 0}::{}"), Err(..)));
+        assert!(matches!(rel_mapping("{a down}::b"), Err(..)));
+    }
+}
@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+
+use super::*;
+
+struct SourceFile {
+    name: Arc<str>,
+    source: String,
+    line_starts: Vec<usize>,
+}
+
+thread_local! {
+    /// The file that is currently being parsed, statements look up their location in it.
+    static SOURCE_FILE: RefCell<Option<SourceFile>> = RefCell::new(None);
+}
+
+/// Runs `f` with statements recording their location relative to the given source.
+pub(super) fn with_source_file<T>(name: &str, source: &str, f: impl FnOnce() -> T) -> T {
+    let line_starts = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect();
+    let file = SourceFile { name: name.into(), source: source.to_string(), line_starts };
+
+    let outer = SOURCE_FILE.with(|v| v.replace(Some(file)));
+    let res = f();
+    SOURCE_FILE.with(|v| v.replace(outer));
+    res
+}
+
+/// The location of `input`, which needs to be a suffix of the source that is being parsed.
+pub(super) fn locate(input: &str) -> Span {
+    SOURCE_FILE.with(|v| match &*v.borrow() {
+        Some(file) if input.len() <= file.source.len() => {
+            let offset = file.source.len() - input.len();
+            let line = file.line_starts.partition_point(|start| *start <= offset);
+            let line_start = file.line_starts[line - 1];
+            let column = file.source[line_start..offset].chars().count() + 1;
+
+            Span { file: Some(file.name.clone()), line, column }
+        }
+        _ => Span::default(),
+    })
+}
+
+/// Additionally returns the location at which the parsed value starts.
+pub(super) fn located<'a, O>(parser: impl Fn(&'a str) -> ResNew<&'a str, O>) -> impl Fn(&'a str) -> ResNew<&'a str, (Span, O)> {
+    move |input| parser(input).map(|(next, (v, last_err))| (next, ((locate(input), v), last_err)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let source = "let a = 1;\n  print(a);";
+
+        assert_eq!(locate(source), Span::default());
+
+        with_source_file("script.m2", source, || {
+            assert_eq!(locate(source), Span { file: Some("script.m2".into()), line: 1, column: 1 });
+            assert_eq!(locate(&source[13..]), Span { file: Some("script.m2".into()), line: 2, column: 3 });
+        });
+    }
+
+    #[test]
+    fn test_block_spans() {
+        let source = "let a = 1;\nif (true) {\n    a = 2;\n}";
+        let block = with_source_file("script.m2", source, || nom_eval(global_block(source)));
+
+        assert_eq!(block.span(0).line, 1);
+        assert_eq!(block.span(1).line, 2);
+        let nested = match &block.statements[1] {
+            Stmt::If(pairs, _) => pairs[0].1.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!((nested.span(0).line, nested.span(0).column), (3, 5));
+    }
+}
@@ -0,0 +1,119 @@
+use super::*;
+
+#[derive(PartialEq, Debug, Clone)]
+pub(super) enum StringPart {
+    Text(String),
+    Interpolation(Expr),
+}
+
+/// `r"..."` or `r#"..."#`, no escapes and no interpolation
+fn raw_string(input: &str) -> ResNew<&str, Vec<StringPart>> {
+    let res: IResult<&str, _, CustomError<&str>> = tuple((char('r'), take_while(|c| c == '#'), char('"')))(input);
+    let (rest, (_, hashes, _)) = res?;
+
+    let terminator = format!("\"{}", hashes);
+    match rest.find(&terminator) {
+        Some(pos) => Ok((
+            &rest[pos + terminator.len()..],
+            (vec![StringPart::Text(rest[..pos].to_string())], None),
+        )),
+        None => Err(make_generic_nom_err_options(input, vec![format!("'{}'", terminator)])),
+    }
+}
+
+fn escaped_string(input: &str) -> ResNew<&str, Vec<StringPart>> {
+    let (mut rest, _) = tag_custom("\"")(input)?;
+    let mut parts = vec![];
+    let mut text = String::new();
+
+    loop {
+        let mut chars = rest.chars();
+        match chars.next() {
+            None => return Err(make_generic_nom_err_options(rest, vec!["'\"'".to_string()])),
+            Some('"') => {
+                rest = chars.as_str();
+                break;
+            }
+            Some('\\') => {
+                match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some(c @ '\\') | Some(c @ '"') | Some(c @ '$') => text.push(c),
+                    // unknown escapes are kept as they are
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => return Err(make_generic_nom_err_options(rest, vec!["'\"'".to_string()])),
+                };
+                rest = chars.as_str();
+            }
+            Some('$') if chars.as_str().starts_with('{') => {
+                let (next, v) = tuple((tag_custom("${"), ws0, expr, ws0, tag_custom("}")))(rest)?;
+                if !text.is_empty() {
+                    parts.push(StringPart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(StringPart::Interpolation(v.2.0));
+                rest = next;
+            }
+            Some(c) => {
+                text.push(c);
+                rest = chars.as_str();
+            }
+        }
+    }
+
+    if !text.is_empty() || parts.is_empty() {
+        parts.push(StringPart::Text(text));
+    }
+
+    Ok((rest, (parts, None)))
+}
+
+/// Shared by ordinary strings and key sequences.
+pub(super) fn string_literal(input: &str) -> ResNew<&str, Vec<StringPart>> {
+    alt((raw_string, escaped_string))(input)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(string_literal(r#""a\"b\\c\nd\te\$""#), nom_ok(vec![
+            StringPart::Text("a\"b\\c\nd\te$".to_string()),
+        ]));
+        assert_eq!(string_literal("\"\""), nom_ok(vec![StringPart::Text("".to_string())]));
+        assert_eq!(string_literal(r#""\q\0""#), nom_ok(vec![StringPart::Text(r"\q\0".to_string())]));
+        assert!(matches!(string_literal("\"abc"), Err(..)));
+    }
+
+    #[test]
+    fn test_raw_string() {
+        assert_eq!(string_literal(r#"r"C:\path\n""#), nom_ok(vec![
+            StringPart::Text(r"C:\path\n".to_string()),
+        ]));
+        assert_eq!(string_literal(r###"r#"say "hi""#"###), nom_ok(vec![
+            StringPart::Text(r#"say "hi""#.to_string()),
+        ]));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        assert_eq!(string_literal("\"sum: ${a + b}!\""), nom_ok(vec![
+            StringPart::Text("sum: ".to_string()),
+            StringPart::Interpolation(Expr::Add(
+                Box::new(Expr::Name("a".to_string())),
+                Box::new(Expr::Name("b".to_string())),
+            )),
+            StringPart::Text("!".to_string()),
+        ]));
+        assert_eq!(string_literal("\"${ \"}\" }\""), nom_ok(vec![
+            StringPart::Interpolation(Expr::Value(ValueType::String("}".to_string()))),
+        ]));
+        assert_eq!(string_literal("\"$a {b}\""), nom_ok(vec![StringPart::Text("$a {b}".to_string())]));
+    }
+}
@@ -20,8 +20,11 @@ pub(super) fn variable_initialization(input: &str) -> ResNew<&str, Expr> {
 
             match expr {
                 Expr::Name(_) | Expr::Value(_) | Expr::Lambda(_, _) | Expr::FunctionCall(_, _) | Expr::Eq(_, _) | Expr::Neq(_, _) |
-                Expr::LT(_, _) | Expr::GT(_, _) | Expr::Add(_, _) | Expr::Sub(_, _) | Expr::Div(_, _) |
-                Expr::Mul(_, _) | Expr::Neg(_) | Expr::And(_, _) | Expr::Or(_, _)
+                Expr::LT(_, _) | Expr::GT(_, _) | Expr::LE(_, _) | Expr::GE(_, _) | Expr::Add(_, _) | Expr::Sub(_, _) |
+                Expr::Div(_, _) | Expr::Mul(_, _) | Expr::Mod(_, _) | Expr::Neg(_) | Expr::Minus(_) |
+                Expr::And(_, _) | Expr::Or(_, _) |
+                Expr::KeyMapping(_) | Expr::GuardedKeyMapping(_, _) | Expr::RepeatKeyMapping(_, _) | Expr::RelMapping(_, _, _) |
+                Expr::TapHoldMapping(_, _) | Expr::ChordMapping(_, _, _) | Expr::SequenceMapping(_, _) | Expr::List(_) | Expr::Map(_) | Expr::Index(_, _)
                 => {}
                 _ => { return Err(make_generic_nom_err_options(input_before_expr, vec!["valid initialization expression".to_string()])); }
             };
@@ -44,7 +47,7 @@ pub(super) fn variable_assignment(input: &str) -> ResNew<&str, Expr> {
 }
 
 pub(super) fn variable(input: &str) -> ResNew<&str, Expr> {
-    ident(input)
+    qualified_ident(input)
         .map(|(next, (name, last_err))|
             (next, (Expr::Name(name), last_err)))
 }
@@ -73,4 +76,14 @@ mod tests {
                    )
                    ));
     }
+
+    #[test]
+    fn test_mapping_handle() {
+        assert_eq!(nom_no_last_err(variable_initialization("let a = x::y")),
+                   nom_ok(Expr::Init(
+                       "a".to_string(),
+                       Box::new(nom_eval(expr("x::y"))),
+                   )
+                   ));
+    }
 }
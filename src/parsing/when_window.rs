@@ -0,0 +1,95 @@
+use regex::Regex;
+
+use super::*;
+
+/// `/regex/`, a slash inside the expression needs to be escaped as `\/`.
+fn regex_literal(input: &str) -> ResNew<&str, WindowPattern> {
+    let (rest, _) = tag_custom("/")(input)?;
+
+    let mut pattern = String::new();
+    let mut chars = rest.char_indices();
+    let next = loop {
+        match chars.next() {
+            Some((idx, '/')) => break &rest[idx + 1..],
+            Some((_, '\\')) => match chars.next() {
+                Some((_, '/')) => pattern.push('/'),
+                Some((_, c)) => {
+                    pattern.push('\\');
+                    pattern.push(c);
+                }
+                None => return Err(make_generic_nom_err_options(input, vec!["regular expression".to_string()])),
+            },
+            Some((_, c)) => pattern.push(c),
+            None => return Err(make_generic_nom_err_options(input, vec!["regular expression".to_string()])),
+        }
+    };
+
+    match Regex::new(&pattern) {
+        Ok(regex) => Ok((next, (WindowPattern::Regex(regex), None))),
+        Err(_) => Err(make_generic_nom_err_options(input, vec!["regular expression".to_string()])),
+    }
+}
+
+fn window_property(input: &str) -> ResNew<&str, (&str, WindowPattern)> {
+    tuple((
+        alt((tag_custom("class"), tag_custom("title"))),
+        ws0,
+        tag_custom(":"),
+        ws0,
+        alt((
+            map(string_value, |(v, last_err)| (WindowPattern::Exact(v), last_err)),
+            regex_literal,
+        )),
+    ))(input).map(|(next, v)| (next, ((v.0, v.4.0), v.4.1)))
+}
+
+pub(super) fn when_window_stmt(input: &str) -> ResNew<&str, Stmt> {
+    let (input, _) = tuple((tag_custom("when_window"), ws0, tag_custom("("), ws0))(input)?;
+    let (input, (first, _)) = window_property(input)?;
+    let (input, rest) = many0(tuple((ws0, tag_custom(","), ws0, window_property)))(input)?;
+    let (input, v) = tuple((ws0, tag_custom(")"), ws0, block))(input)?;
+
+    let mut condition = WindowCondition::default();
+    for (name, pattern) in std::iter::once(first).chain(rest.into_iter().map(|v| v.3.0)) {
+        match name {
+            "class" => condition.class = Some(pattern),
+            _ => condition.title = Some(pattern),
+        }
+    }
+
+    let (block, last_err) = v.3;
+    Ok((input, (Stmt::WhenWindow(condition, block), last_err)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_when_window() {
+        assert_eq!(nom_no_last_err(when_window_stmt("when_window(class: \"firefox\", title: /GitHub$/) { a::b; }")), nom_ok(Stmt::WhenWindow(
+            WindowCondition {
+                class: Some(WindowPattern::Exact("firefox".to_string())),
+                title: Some(WindowPattern::Regex(Regex::new("GitHub$").unwrap())),
+            },
+            nom_eval(block("{ a::b; }")),
+        )));
+
+        assert_eq!(nom_no_last_err(when_window_stmt("when_window( title : \"vim\" ){}")), nom_ok(Stmt::WhenWindow(
+            WindowCondition { class: None, title: Some(WindowPattern::Exact("vim".to_string())) },
+            Block::new(),
+        )));
+
+        assert!(matches!(when_window_stmt("when_window(){}"), Err(..)));
+        assert!(matches!(when_window_stmt("when_window(name: \"vim\"){}"), Err(..)));
+        assert!(matches!(when_window_stmt("when_window(title: /(/){}"), Err(..)));
+        assert!(matches!(when_window_stmt("when_window(title: /abc){}"), Err(..)));
+    }
+
+    #[test]
+    fn test_regex_literal() {
+        assert_eq!(regex_literal(r"/a\/b\.c/"), nom_ok(WindowPattern::Regex(Regex::new(r"a/b\.c").unwrap())));
+        assert_eq!(regex_literal(r"/\d+/ rest"), Ok((" rest", (WindowPattern::Regex(Regex::new(r"\d+").unwrap()), None))));
+    }
+}
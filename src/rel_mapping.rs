@@ -0,0 +1,145 @@
+use std::cmp::Ordering;
+
+use evdev_rs::enums::EV_REL;
+
+use crate::*;
+
+/// A trigger for relative events of mice and wheels, i.e. `{wheel_up}` or `{rel_x > 0}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RelTrigger {
+    pub axis: EventCode,
+    /// how the event value compares to the threshold, `>=` and `<=` are stored as `>` and `<`
+    pub ordering: Ordering,
+    pub threshold: i32,
+}
+
+impl RelTrigger {
+    pub fn new(axis: EV_REL, ordering: Ordering, threshold: i32) -> Self {
+        RelTrigger { axis: EventCode::EV_REL(axis), ordering, threshold }
+    }
+
+    fn matches(&self, axis: &EventCode, value: i32) -> bool {
+        self.axis == *axis && value.cmp(&self.threshold) == self.ordering
+    }
+}
+
+/// Returns the high-resolution axis that is reported along with a wheel axis.
+pub fn hi_res_axis(axis: &EventCode) -> Option<EventCode> {
+    match axis {
+        EventCode::EV_REL(EV_REL::REL_WHEEL) => Some(EventCode::EV_REL(EV_REL::REL_WHEEL_HI_RES)),
+        EventCode::EV_REL(EV_REL::REL_HWHEEL) => Some(EventCode::EV_REL(EV_REL::REL_HWHEEL_HI_RES)),
+        _ => None,
+    }
+}
+
+fn lo_res_axis(axis: &EventCode) -> Option<EventCode> {
+    match axis {
+        EventCode::EV_REL(EV_REL::REL_WHEEL_HI_RES) => Some(EventCode::EV_REL(EV_REL::REL_WHEEL)),
+        EventCode::EV_REL(EV_REL::REL_HWHEEL_HI_RES) => Some(EventCode::EV_REL(EV_REL::REL_HWHEEL)),
+        _ => None,
+    }
+}
+
+/// The mapping targets for a relative event.
+pub struct RelLookup {
+    /// the axis whose handlers the event waits for
+    pub axis: Key,
+    pub targets: Arc<Vec<MappingTarget>>,
+    /// high-resolution wheel events belong to the mappings of the wheel axis, they are dropped if
+    /// a mapping applies but don't run it a second time
+    pub hi_res: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct RelMappings(Vec<(RelTrigger, Arc<Vec<MappingTarget>>)>);
+
+impl RelMappings {
+    pub fn new() -> Self { RelMappings(vec![]) }
+
+    /// Adds a mapping target, the targets of a trigger are ordered the same as the ones of keys.
+    pub fn insert(&mut self, trigger: RelTrigger, target: MappingTarget) {
+        match self.0.iter_mut().find(|(v, _)| *v == trigger) {
+            Some((_, targets)) => *targets = Arc::new(with_target(targets, target)),
+            None => self.0.push((trigger, Arc::new(vec![target]))),
+        }
+    }
+
+    pub fn remove_id(&mut self, id: usize) -> bool {
+        let mut removed = false;
+        for (_, targets) in self.0.iter_mut().filter(|(_, targets)| targets.iter().any(|v| v.id == id)) {
+            removed = true;
+            *targets = Arc::new(targets.iter().filter(|v| v.id != id).cloned().collect());
+        }
+        self.0.retain(|(_, targets)| !targets.is_empty());
+        removed
+    }
+
+    pub fn clear(&mut self) { self.0.clear(); }
+
+    /// Returns the targets of every trigger that matches the event in the order the triggers were
+    /// added, the event value is available to the targets as `value`.
+    pub fn lookup(&self, ev: &InputEvent) -> Option<RelLookup> {
+        let (axis, value, hi_res) = match lo_res_axis(&ev.event_code) {
+            Some(axis) => (axis, ev.value.signum(), true),
+            None => (ev.event_code, ev.value, false),
+        };
+
+        let targets: Vec<MappingTarget> = self.0.iter()
+            .filter(|(trigger, _)| trigger.matches(&axis, value))
+            .flat_map(|(_, targets)| targets.iter())
+            .map(|target| {
+                let var_map = target.var_map.child();
+                var_map.define("value", ValueType::Number(ev.value as f64));
+                MappingTarget { var_map, ..target.clone() }
+            })
+            .collect();
+
+        if targets.is_empty() { return None; }
+        Some(RelLookup { axis: Key { event_code: axis }, targets: Arc::new(targets), hi_res })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(id: usize) -> MappingTarget {
+        MappingTarget { id, guard: None, window: None, repeat: None, block: Block::new(), var_map: Environment::new() }
+    }
+
+    fn ev(axis: EV_REL, value: i32) -> InputEvent {
+        InputEvent { event_code: EventCode::EV_REL(axis), value, time: INPUT_EV_DUMMY_TIME }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut mappings = RelMappings::new();
+        mappings.insert(RelTrigger::new(EV_REL::REL_WHEEL, Ordering::Greater, 0), target(1));
+        mappings.insert(RelTrigger::new(EV_REL::REL_X, Ordering::Greater, 10), target(2));
+        mappings.insert(RelTrigger::new(EV_REL::REL_X, Ordering::Greater, 0), target(3));
+
+        assert!(mappings.lookup(&ev(EV_REL::REL_WHEEL, -1)).is_none());
+        let lookup = mappings.lookup(&ev(EV_REL::REL_WHEEL, 1)).unwrap();
+        assert!(!lookup.hi_res);
+        assert_eq!(lookup.targets[0].var_map.get("value"), Some(ValueType::Number(1.0)));
+
+        let ids = |lookup: RelLookup| lookup.targets.iter().map(|v| v.id).collect::<Vec<_>>();
+        assert_eq!(ids(mappings.lookup(&ev(EV_REL::REL_X, 20)).unwrap()), vec![2, 3]);
+        assert_eq!(ids(mappings.lookup(&ev(EV_REL::REL_X, 5)).unwrap()), vec![3]);
+
+        assert!(mappings.remove_id(3));
+        assert!(mappings.lookup(&ev(EV_REL::REL_X, 5)).is_none());
+    }
+
+    #[test]
+    fn test_hi_res() {
+        let mut mappings = RelMappings::new();
+        mappings.insert(RelTrigger::new(EV_REL::REL_WHEEL, Ordering::Less, 0), target(1));
+
+        let lookup = mappings.lookup(&ev(EV_REL::REL_WHEEL_HI_RES, -120)).unwrap();
+        assert!(lookup.hi_res);
+        assert_eq!(lookup.axis, Key { event_code: EventCode::EV_REL(EV_REL::REL_WHEEL) });
+        assert!(mappings.lookup(&ev(EV_REL::REL_WHEEL_HI_RES, 120)).is_none());
+    }
+}
@@ -1,31 +1,38 @@
-use evdev_rs::enums::int_to_ev_key;
+use evdev_rs::enums::{int_to_ev_key, EventType};
 use tokio::process::Command;
 
 use crate::*;
 use crate::messaging::ExecutionMessage;
-use crate::parsing::parser::{parse_key_action_with_mods, parse_key_sequence};
-
-pub async fn throw_error<'a>(err: anyhow::Error, exit_code: i32, amb: &mut Ambient<'a>) -> ValueType {
-    amb.message_tx.borrow_mut().as_ref().unwrap()
-        .send(ExecutionMessage::FatalError(err, exit_code))
-        .await.unwrap();
-    return ValueType::Void;
+use crate::parsing::parser::{parse_key_action_with_mods, parse_key_click_action_with_mods, parse_key_sequence};
+
+/// The minimum and maximum number of arguments a built-in function accepts, `None` if there's no
+/// built-in function with the given name.
+pub(crate) fn builtin_arity(name: &str) -> Option<(usize, Option<usize>)> {
+    let arity = match name {
+        "active_window_class" | "unmap_all" | "mappings" | "channel" => (0, Some(0)),
+        "exit" => (0, Some(1)),
+        "send" | "send_modifier" | "escape_key_sequence" | "on_window_change" | "set_chord_window" | "set_leader" | "set_sequence_timeout" | "set_handler_order" |
+        "layer_on" | "layer_off" | "layer_toggle" | "layer_one_shot" | "sleep" | "print" | "number_to_key" |
+        "number_to_char" | "char_to_number" | "len" | "pop" | "keys" | "unmap" | "clear_timer" | "spawn" | "cancel" | "join" | "recv" => (1, Some(1)),
+        "sticky_modifiers" => (1, Some(2)),
+        "layer" | "layer_momentary" | "push" | "remove" | "map_key" | "set_timeout" | "set_interval" | "send_to" | "send_rel" => (2, Some(2)),
+        "insert" => (3, Some(3)),
+        "execute" => (1, None),
+        _ => return None,
+    };
+    Some(arity)
 }
 
-pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &GuardedVarMap, amb: &mut Ambient<'_>) -> Result<ValueType> {
+pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Environment, amb: &mut Ambient<'_>) -> Result<ValueType> {
     let mut parsed_args = vec![];
     for expr in args {
-        let arg = eval_expr(expr, var_map, amb).await;
+        let arg = eval_expr(expr, var_map, amb).await?;
         parsed_args.push(arg);
     }
 
     match &**name {
         "exit" => {
-            let arg = args.get(0);
-            let val = match arg {
-                Some(arg) => eval_expr(arg, var_map, amb).await,
-                _ => ValueType::Number(0.0),
-            };
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Number(0.0));
 
             let exit_code = match val {
                 ValueType::Number(exit_code) => exit_code as i32,
@@ -35,27 +42,37 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
             amb.message_tx.as_ref().unwrap().send(ExecutionMessage::Exit(exit_code)).await.unwrap();
         }
         "send" => {
-            let val = eval_expr(args.get(0).unwrap(), var_map, amb).await;
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void);
             let val = match val {
                 ValueType::String(val) => val,
                 _ => return Err(anyhow!("invalid parameter passed to function 'send'")),
             };
 
-            let actions = parse_key_sequence(&*val).unwrap();
+            let actions = parse_key_sequence(&*val)?;
 
             for action in actions {
                 amb.ev_writer_tx.send(action.to_input_ev()).await.unwrap();
                 amb.ev_writer_tx.send(SYN_REPORT.clone()).await.unwrap();
             }
         }
+        "escape_key_sequence" => {
+            // like string interpolation, any value is typed the way it's printed
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void).to_string();
+
+            let escaped = val.chars().map(|c| match c {
+                '{' | '}' => format!("{{{}}}", c),
+                c => c.to_string(),
+            }).collect();
+            return Ok(ValueType::String(escaped));
+        }
         "send_modifier" => {
-            let val = eval_expr(args.get(0).unwrap(), var_map, amb).await;
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void);
             let val = match val {
                 ValueType::String(val) => val,
                 _ => return Err(anyhow!("invalid parameter passed to function 'send'")),
             };
 
-            let actions = parse_key_sequence(&*val).unwrap();
+            let actions = parse_key_sequence(&*val)?;
 
             if actions.len() != 1 {
                 return Err(anyhow!("expected a single key action, got {}", actions.len()));
@@ -63,8 +80,7 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
 
             let action = actions.get(0).unwrap();
 
-            if [*KEY_LEFT_CTRL, *KEY_RIGHT_CTRL, *KEY_LEFT_ALT, *KEY_RIGHT_ALT, *KEY_LEFT_SHIFT, *KEY_RIGHT_SHIFT, *KEY_LEFT_META, *KEY_RIGHT_META]
-                .contains(&action.key) {
+            if action.key.is_modifier() {
                 amb.message_tx.as_ref().unwrap().send(ExecutionMessage::UpdateModifiers(*action)).await.unwrap();
             } else {
                 return Err(anyhow!("key action needs to be a modifier event"));
@@ -73,6 +89,25 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
             amb.ev_writer_tx.send(action.to_input_ev()).await.unwrap();
             amb.ev_writer_tx.send(SYN_REPORT.clone()).await.unwrap();
         }
+        "send_rel" => {
+            let (axis, value) = match (parsed_args.get(0).cloned(), parsed_args.get(1).cloned()) {
+                (Some(ValueType::String(axis)), Some(ValueType::Number(value))) => (axis, value as i32),
+                _ => return Err(anyhow!("invalid arguments passed to function 'send_rel'")),
+            };
+            let axis = match EventCode::from_str(&EventType::EV_REL, &axis.to_uppercase()) {
+                Some(axis) => axis,
+                _ => return Err(anyhow!("unknown relative axis '{}'", axis)),
+            };
+
+            amb.ev_writer_tx.send(InputEvent { event_code: axis, value, time: INPUT_EV_DUMMY_TIME }).await.unwrap();
+            // wheels are also reported in high resolution, applications that use it ignore the other event
+            if let Some(hi_res_axis) = hi_res_axis(&axis) {
+                amb.ev_writer_tx.send(InputEvent { event_code: hi_res_axis, value: value * 120, time: INPUT_EV_DUMMY_TIME }).await.unwrap();
+            }
+            if !amb.rel_frame {
+                amb.ev_writer_tx.send(SYN_REPORT.clone()).await.unwrap();
+            }
+        }
         "active_window_class" => {
             let (tx, mut rx) = mpsc::channel(1);
             amb.message_tx.as_ref().unwrap().send(ExecutionMessage::GetFocusedWindowInfo(tx)).await.unwrap();
@@ -87,7 +122,7 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
 
             let inner_block;
             let inner_var_map;
-            if let ValueType::Lambda(_, _block, _var_map) = eval_expr(args.get(0).unwrap(), var_map, amb).await {
+            if let ValueType::Lambda(_, _block, _var_map) = parsed_args.get(0).cloned().unwrap_or(ValueType::Void) {
                 inner_block = _block;
                 inner_var_map = _var_map;
             } else {
@@ -96,15 +131,178 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
 
             amb.message_tx.as_ref().unwrap().send(ExecutionMessage::RegisterWindowChangeCallback(inner_block, inner_var_map)).await.unwrap();
         }
+        "set_chord_window" => {
+            let millis = match parsed_args.get(0) {
+                Some(ValueType::Number(millis)) => *millis,
+                _ => return Err(anyhow!("set_chord_window expects a number argument")),
+            };
+
+            amb.message_tx.as_ref().unwrap()
+                .send(ExecutionMessage::SetChordWindow(time::Duration::from_millis(millis as u64))).await.unwrap();
+        }
+        "set_leader" => {
+            let leader = match parsed_args.get(0) {
+                Some(ValueType::String(leader)) => parse_key_click_action_with_mods(leader)?,
+                _ => return Err(anyhow!("set_leader expects a key argument")),
+            };
+
+            amb.message_tx.as_ref().unwrap().send(ExecutionMessage::SetLeader(leader)).await.unwrap();
+        }
+        "set_sequence_timeout" => {
+            let millis = match parsed_args.get(0) {
+                Some(ValueType::Number(millis)) => *millis,
+                _ => return Err(anyhow!("set_sequence_timeout expects a number argument")),
+            };
+
+            amb.message_tx.as_ref().unwrap()
+                .send(ExecutionMessage::SetSequenceTimeout(time::Duration::from_millis(millis as u64))).await.unwrap();
+        }
+        "set_handler_order" => {
+            let order = match parsed_args.get(0) {
+                Some(ValueType::String(order)) if order == "key" => HandlerOrder::PerKey,
+                Some(ValueType::String(order)) if order == "global" => HandlerOrder::Global,
+                _ => return Err(anyhow!("set_handler_order expects either \"key\" or \"global\"")),
+            };
+
+            amb.message_tx.as_ref().unwrap().send(ExecutionMessage::SetHandlerOrder(order)).await.unwrap();
+        }
+        "layer" => {
+            let (name, block, lambda_var_map) = match (parsed_args.get(0), parsed_args.get(1)) {
+                (Some(ValueType::String(name)), Some(ValueType::Lambda(_, block, var_map))) => (name.clone(), block.clone(), var_map.clone()),
+                _ => return Err(anyhow!("invalid arguments passed to 'layer'")),
+            };
+
+            // mappings defined in the callback are added to the layer
+            let outer_layer = amb.layer.replace(name);
+            let ret = eval_block(&block, &lambda_var_map, amb).await;
+            amb.layer = outer_layer;
+            ret?;
+        }
+        "layer_on" | "layer_off" | "layer_toggle" | "layer_one_shot" => {
+            let layer = match parsed_args.get(0) {
+                Some(ValueType::String(layer)) => layer.clone(),
+                _ => return Err(anyhow!("{} expects a layer name argument", name)),
+            };
+
+            let msg = match &**name {
+                "layer_on" => ExecutionMessage::LayerOn(layer),
+                "layer_off" => ExecutionMessage::LayerOff(layer),
+                "layer_toggle" => ExecutionMessage::LayerToggle(layer),
+                _ => ExecutionMessage::LayerOneShot(layer),
+            };
+            amb.message_tx.as_ref().unwrap().send(msg).await.unwrap();
+        }
+        "layer_momentary" => {
+            let (key, layer) = match (parsed_args.get(0), parsed_args.get(1)) {
+                (Some(ValueType::String(key)), Some(ValueType::String(layer))) => (key, layer.clone()),
+                _ => return Err(anyhow!("invalid arguments passed to 'layer_momentary'")),
+            };
+
+            let key = parse_key_click_action_with_mods(key)?;
+            if key.modifiers != KeyModifierFlags::new() {
+                return Err(anyhow!("layer_momentary expects a key without modifiers"));
+            }
+
+            amb.message_tx.as_ref().unwrap()
+                .send(ExecutionMessage::AddMomentaryLayer(amb.window_cycle_token, key.key, layer)).await.unwrap();
+        }
+        "sticky_modifiers" => {
+            let (enabled, lock_on_double_tap) = match (parsed_args.get(0), parsed_args.get(1)) {
+                (Some(ValueType::Bool(enabled)), None) => (*enabled, false),
+                (Some(ValueType::Bool(enabled)), Some(ValueType::Bool(lock_on_double_tap))) => (*enabled, *lock_on_double_tap),
+                _ => return Err(anyhow!("invalid arguments passed to 'sticky_modifiers'")),
+            };
+
+            amb.message_tx.as_ref().unwrap()
+                .send(ExecutionMessage::SetStickyModifiers(enabled, lock_on_double_tap)).await.unwrap();
+        }
+        "set_timeout" | "set_interval" => {
+            let (millis, block, lambda_var_map) = match (parsed_args.get(0), parsed_args.get(1)) {
+                (Some(ValueType::Number(millis)), Some(ValueType::Lambda(_, block, var_map))) => (*millis, block.clone(), var_map.clone()),
+                _ => return Err(anyhow!("{} expects a number and a lambda argument", name)),
+            };
+
+            let repeat = name == "set_interval";
+            if repeat && millis < 1.0 {
+                return Err(anyhow!("set_interval expects an interval of at least 1 millisecond"));
+            }
+
+            let id = next_timer_id();
+            let timer = Timer { block, var_map: lambda_var_map, delay: time::Duration::from_millis(millis.max(0.0) as u64), repeat };
+            amb.message_tx.as_ref().unwrap().send(ExecutionMessage::AddTimer(id, timer)).await.unwrap();
+
+            // the id is returned as a handle that can be used to clear the timer again
+            return Ok(ValueType::Timer(id));
+        }
+        "clear_timer" => {
+            let id = match parsed_args.get(0) {
+                Some(ValueType::Timer(id)) => *id,
+                Some(value) => return Err(anyhow!("clear_timer expects a timer handle, got {}", value.type_name())),
+                None => return Err(anyhow!("clear_timer expects a timer handle")),
+            };
+
+            amb.message_tx.as_ref().unwrap().send(ExecutionMessage::ClearTimer(id)).await.unwrap();
+        }
+        "spawn" => {
+            let (block, lambda_var_map) = match parsed_args.get(0) {
+                Some(ValueType::Lambda(params, block, var_map)) if params.is_empty() => (block.clone(), var_map.clone()),
+                _ => return Err(anyhow!("spawn expects a lambda without parameters")),
+            };
+
+            let mut message_tx = amb.message_tx.as_ref().unwrap().deref().clone();
+            let ev_writer_tx = amb.ev_writer_tx.clone();
+            let window_cycle_token = amb.window_cycle_token;
+            let modifier_state = amb.modifier_state.clone();
+            let (layer, window) = (amb.layer.clone(), amb.window.clone());
+
+            let task = ScriptTask::spawn(async move {
+                let mut amb = Ambient { ev_writer_tx, window_cycle_token, message_tx: Some(&mut message_tx), modifier_state: &modifier_state, layer, window, rel_frame: false };
+                let ret = eval_block(&block, &lambda_var_map.child(), &mut amb).await;
+                drop(amb);
+
+                match ret {
+                    Ok(BlockRet::Return(value)) => value,
+                    Ok(_) => ValueType::Void,
+                    Err(err) => {
+                        let _ = message_tx.send(ExecutionMessage::RuntimeError(err)).await;
+                        ValueType::Void
+                    }
+                }
+            });
+
+            return Ok(ValueType::Task(task));
+        }
+        "cancel" | "join" => {
+            let task = match parsed_args.get(0) {
+                Some(ValueType::Task(task)) => task.clone(),
+                _ => return Err(anyhow!("{} expects a task argument", name)),
+            };
+
+            if name == "join" { return Ok(task.join().await); }
+            task.cancel();
+        }
+        "channel" => return Ok(ValueType::Channel(ScriptChannel::new())),
+        "send_to" => {
+            match (parsed_args.get(0), parsed_args.get(1)) {
+                (Some(ValueType::Channel(channel)), Some(value)) => channel.send(value.clone()),
+                _ => return Err(anyhow!("send_to expects a channel and a value argument")),
+            }
+        }
+        "recv" => {
+            return match parsed_args.get(0) {
+                Some(ValueType::Channel(channel)) => Ok(channel.recv().await),
+                _ => Err(anyhow!("recv expects a channel argument")),
+            };
+        }
         "sleep" => {
-            let val = eval_expr(args.get(0).unwrap(), var_map, amb).await;
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void);
             match val {
                 ValueType::Number(millis) => tokio::time::sleep(time::Duration::from_millis(millis as u64)).await,
                 _ => return Err(anyhow!("sleep expects a number argument")),
             }
         }
         "print" => {
-            let val = eval_expr(args.get(0).unwrap(), var_map, amb).await;
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void);
             let val = format!("{}\n", val);
 
             amb.message_tx.borrow_mut().as_ref().unwrap()
@@ -112,19 +310,19 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
                 .unwrap();
         }
         "number_to_key" => {
-            let val = eval_expr(args.get(0).unwrap(), var_map, amb).await;
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void);
             let val = match val {
                 ValueType::Number(val) => val,
                 _ => return Err(anyhow!("only numbers can be converted to keys")),
             };
             let val = val as u32;
 
-            let key = int_to_ev_key(val).expect(&*format!("key for scan code '{}' not found", val));
+            let key = int_to_ev_key(val).ok_or_else(|| anyhow!("key for scan code '{}' not found", val))?;
 
             return Ok(ValueType::String(format!("{{{}}}", EventCode::EV_KEY(key).to_string())));
         }
         "number_to_char" => {
-            let val = eval_expr(args.get(0).unwrap(), var_map, amb).await;
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void);
             let val = match val {
                 ValueType::Number(val) => val,
                 _ => return Err(anyhow!("only numbers can be converted to chars")),
@@ -134,39 +332,110 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
             return Ok(ValueType::String(format!("{}", val)));
         }
         "char_to_number" => {
-            let val = eval_expr(args.get(0).unwrap(), var_map, amb).await;
+            let val = parsed_args.get(0).cloned().unwrap_or(ValueType::Void);
             let val = match val {
                 ValueType::String(val) => val,
                 _ => return Err(anyhow!("only chars can be converted to chars")),
             };
-            if val.len() != 1 { panic!("string needs to contain exactly 1 character") }
+            if val.len() != 1 { return Err(anyhow!("string needs to contain exactly 1 character")); }
 
             let first_ch = val.chars().next().unwrap();
             let val = first_ch as u8 as f64;
             return Ok(ValueType::Number(val));
         }
+        "len" => {
+            return match parsed_args.get(0) {
+                Some(ValueType::List(list)) => Ok(ValueType::Number(list.lock().unwrap().len() as f64)),
+                Some(ValueType::Map(map)) => Ok(ValueType::Number(map.lock().unwrap().len() as f64)),
+                Some(ValueType::String(v)) => Ok(ValueType::Number(v.chars().count() as f64)),
+                _ => Err(anyhow!("function 'len' expects a list, map or string argument")),
+            };
+        }
+        "push" => {
+            match (parsed_args.get(0), parsed_args.get(1)) {
+                (Some(ValueType::List(list)), Some(value)) => list.lock().unwrap().push(value.clone()),
+                _ => return Err(anyhow!("invalid arguments passed to 'push'")),
+            }
+        }
+        "pop" => {
+            return match parsed_args.get(0) {
+                Some(ValueType::List(list)) => Ok(list.lock().unwrap().pop().unwrap_or(ValueType::Void)),
+                _ => Err(anyhow!("function 'pop' expects a list argument")),
+            };
+        }
+        "insert" => {
+            match (parsed_args.get(0), parsed_args.get(1), parsed_args.get(2)) {
+                (Some(ValueType::Map(map)), Some(ValueType::String(key)), Some(value)) => {
+                    map.lock().unwrap().insert(key.clone(), value.clone());
+                }
+                _ => return Err(anyhow!("invalid arguments passed to 'insert'")),
+            }
+        }
+        "remove" => {
+            return match (parsed_args.get(0), parsed_args.get(1)) {
+                (Some(ValueType::Map(map)), Some(ValueType::String(key))) => Ok(map.lock().unwrap().remove(key).unwrap_or(ValueType::Void)),
+                _ => Err(anyhow!("invalid arguments passed to 'remove'")),
+            };
+        }
+        "keys" => {
+            return match parsed_args.get(0) {
+                Some(ValueType::Map(map)) => {
+                    let keys = map.lock().unwrap().keys().cloned().map(ValueType::String).collect();
+                    Ok(ValueType::List(GuardedList::new(Mutex::new(keys))))
+                }
+                _ => Err(anyhow!("function 'keys' expects a map argument")),
+            };
+        }
         "map_key" => {
             let val = (
-                eval_expr(args.get(0).unwrap(), var_map, amb).await,
-                eval_expr(args.get(1).unwrap(), var_map, amb).await,
+                parsed_args.get(0).cloned().unwrap_or(ValueType::Void),
+                parsed_args.get(1).cloned().unwrap_or(ValueType::Void),
             );
             let (from, to) = match val {
                 (ValueType::String(from), ValueType::Lambda(_, to, var_map)) => (from, (to, var_map)),
                 _ => return Err(anyhow!("invalid arguments passed to 'map_key'")),
             };
 
-            let mappings = match parse_key_action_with_mods(&*from, to.0).unwrap() {
+            let mappings = match parse_key_action_with_mods(&*from, to.0)? {
                 Expr::KeyMapping(v) => v,
                 _ => unreachable!(),
             };
 
+            let id = next_mapping_id();
             for mapping in mappings {
                 let mapping = mapping.clone();
+                let target = MappingTarget { id, guard: None, window: amb.window.clone(), repeat: None, block: mapping.to, var_map: to.1.clone() };
 
                 amb.message_tx.borrow_mut().as_ref().unwrap()
-                    .send(ExecutionMessage::AddMapping(amb.window_cycle_token, mapping.from, mapping.to, to.1.clone())).await
+                    .send(ExecutionMessage::AddMapping(amb.window_cycle_token, amb.layer.clone(), mapping.from, target)).await
                     .unwrap();
             }
+
+            // the handle can be passed to 'unmap' to remove exactly this mapping
+            return Ok(ValueType::Mapping(id));
+        }
+        "unmap" => {
+            let msg = match parsed_args.get(0) {
+                Some(ValueType::String(from)) => {
+                    let triggers = match parse_key_action_with_mods(&*from, Block::new())? {
+                        Expr::KeyMapping(v) => v.into_iter().map(|v| v.from).collect(),
+                        _ => unreachable!(),
+                    };
+                    ExecutionMessage::RemoveMapping(amb.layer.clone(), triggers)
+                }
+                Some(ValueType::Mapping(id)) => ExecutionMessage::RemoveMappingById(*id),
+                _ => return Err(anyhow!("invalid arguments passed to 'unmap'")),
+            };
+            amb.message_tx.as_ref().unwrap().send(msg).await.unwrap();
+        }
+        "unmap_all" => {
+            amb.message_tx.as_ref().unwrap().send(ExecutionMessage::RemoveAllMappings(amb.layer.clone())).await.unwrap();
+        }
+        "mappings" => {
+            let (tx, mut rx) = mpsc::channel(1);
+            amb.message_tx.as_ref().unwrap().send(ExecutionMessage::GetMappings(amb.layer.clone(), tx)).await.unwrap();
+            let triggers = rx.recv().await.unwrap().into_iter().map(ValueType::String).collect();
+            return Ok(ValueType::List(GuardedList::new(Mutex::new(triggers))));
         }
         "execute" => {
             if parsed_args.len() < 1 { return Err(anyhow!("argument error: function 'execute' expected at least 1 argument")); }
@@ -198,31 +467,28 @@ pub async fn evaluate_builtin<'a>(name: &String, args: &Vec<Expr>, var_map: &Gua
             return Ok(ValueType::String(output.to_string()));
         }
         name => {
-            let (lambda_params, lambda_block, lambda_var_map) = match eval_expr(&Expr::Name(name.to_string()), var_map, amb).await {
+            let (lambda_params, lambda_block, lambda_var_map) = match eval_expr(&Expr::Name(name.to_string()), var_map, amb).await? {
                 ValueType::Lambda(params, block, var_map) => (params, block, var_map),
                 ValueType::Void => return Err(anyhow!("function '{}' not found in this scope", name)),
                 _ => return Err(anyhow!("variable '{}' is not a lambda function", name)),
             };
 
-            // we need to clone the lambda's var_map since each lambda execution needs to not affect the next one
-            // TODO make GuardedVarMap a proper struct and implement a proper deep clone method
-            let mut lambda_var_map = GuardedVarMap::new(Mutex::new(VarMap::new(
-                lambda_var_map.lock().unwrap().parent.clone()
-            )));
+            if args.len() != lambda_params.len() {
+                return Err(anyhow!("function '{}' expects {} argument(s), got {}", name, lambda_params.len(), args.len()));
+            }
 
-            for (idx, param) in lambda_params.iter().enumerate() {
-                let val = match args.get(idx) {
-                    Some(expr) => eval_expr(expr, var_map, amb).await,
-                    None => ValueType::Void,
-                };
+            // every call gets a fresh frame for its parameters, the captured scope is shared
+            let lambda_var_map = lambda_var_map.child();
 
-                eval_expr(&Expr::Init(param.clone(), Box::new(Expr::Value(val))), &lambda_var_map, amb).await;
+            for (param, val) in lambda_params.iter().zip(parsed_args) {
+                lambda_var_map.define(param, val);
             }
 
-            let ret = eval_block(&lambda_block, &mut lambda_var_map, amb).await;
+            let ret = eval_block(&lambda_block, &lambda_var_map, amb).await?;
             match ret {
                 BlockRet::Return(ret) => return Ok(ret),
                 BlockRet::Continue => return Err(anyhow!("function cannot return a continue statement")),
+                BlockRet::Break => return Err(anyhow!("function cannot return a break statement")),
                 BlockRet::None => {}
             }
         }
@@ -0,0 +1,101 @@
+use std::fmt;
+use std::future::Future;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::*;
+
+/// A task started by `spawn`, the handle is shared by every value that refers to the task.
+pub struct ScriptTask {
+    handle: Mutex<JoinHandle<()>>,
+    /// the return value of the task once it finished, the sender is dropped if it got cancelled
+    result: watch::Receiver<Option<ValueType>>,
+}
+
+impl ScriptTask {
+    pub fn spawn(f: impl Future<Output=ValueType> + Send + 'static) -> Arc<Self> {
+        let (result_tx, result) = watch::channel(None);
+        let handle = task::spawn(async move {
+            let value = f.await;
+            let _ = result_tx.send(Some(value));
+        });
+
+        Arc::new(ScriptTask { handle: Mutex::new(handle), result })
+    }
+
+    /// Stops the task the next time it waits, e.g. in `sleep` or `recv`.
+    pub fn cancel(&self) { self.handle.lock().unwrap().abort(); }
+
+    /// Waits for the task to end, cancelled tasks return void.
+    pub async fn join(&self) -> ValueType {
+        let mut result = self.result.clone();
+        loop {
+            if let Some(value) = &*result.borrow() { return value.clone(); }
+            if result.changed().await.is_err() {
+                return result.borrow().clone().unwrap_or(ValueType::Void);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ScriptTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "ScriptTask") }
+}
+
+/// An unbounded queue of values, any number of tasks can send and receive through it.
+pub struct ScriptChannel {
+    tx: mpsc::UnboundedSender<ValueType>,
+    rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<ValueType>>,
+}
+
+impl ScriptChannel {
+    pub fn new() -> Arc<Self> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Arc::new(ScriptChannel { tx, rx: tokio::sync::Mutex::new(rx) })
+    }
+
+    pub fn send(&self, value: ValueType) {
+        // the receiver lives as long as the channel, sending can't fail
+        let _ = self.tx.send(value);
+    }
+
+    /// Waits for the next value, receivers take turns if several tasks wait at the same time.
+    pub async fn recv(&self) -> ValueType {
+        self.rx.lock().await.recv().await.unwrap_or(ValueType::Void)
+    }
+}
+
+impl fmt::Debug for ScriptChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "ScriptChannel") }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_join() {
+        let task = ScriptTask::spawn(async { ValueType::Number(1.0) });
+        assert_eq!(task.join().await, ValueType::Number(1.0));
+        // joining again returns the same value
+        assert_eq!(task.join().await, ValueType::Number(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let channel = ScriptChannel::new();
+        let task = {
+            let channel = channel.clone();
+            ScriptTask::spawn(async move { channel.recv().await })
+        };
+
+        task.cancel();
+        assert!(matches!(task.join().await, ValueType::Void));
+
+        // values aren't lost by cancelled receivers
+        channel.send(ValueType::Bool(true));
+        assert_eq!(channel.recv().await, ValueType::Bool(true));
+    }
+}
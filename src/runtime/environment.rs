@@ -0,0 +1,201 @@
+use std::fmt;
+
+use crate::*;
+
+struct Scope {
+    values: Mutex<HashMap<String, Binding>>,
+    parent: Option<Environment>,
+}
+
+enum Binding {
+    Value(ValueType),
+    /// A function declaration, it captures the scope that holds it whenever it's looked up. Storing
+    /// a lambda instead would keep the scope alive through its own contents.
+    Function(Vec<String>, Block),
+}
+
+/// The variables that are visible at some point of a script, a chain of scopes that ends at the
+/// global scope.
+///
+/// Environments are shared by reference: lambdas capture the environment they are defined in, so
+/// changes to captured variables are seen by the lambda and by the code that defined it alike.
+/// Every call of a lambda runs in a fresh child environment that holds its parameters and locals.
+#[derive(Clone)]
+pub struct Environment(Arc<Scope>);
+
+impl Environment {
+    /// An empty global scope.
+    pub fn new() -> Self {
+        Environment(Arc::new(Scope { values: Mutex::new(HashMap::new()), parent: None }))
+    }
+
+    /// A nested scope, variables defined in it are dropped with it.
+    pub fn child(&self) -> Self {
+        Environment(Arc::new(Scope { values: Mutex::new(HashMap::new()), parent: Some(self.clone()) }))
+    }
+
+    /// Defines a variable in this scope, shadowing variables of outer scopes with the same name.
+    pub fn define(&self, name: &str, value: ValueType) {
+        self.0.values.lock().unwrap().insert(name.to_string(), Binding::Value(value));
+    }
+
+    /// Defines a function in this scope, looking it up results in a lambda that captures this scope.
+    pub fn define_function(&self, name: &str, params: Vec<String>, block: Block) {
+        self.0.values.lock().unwrap().insert(name.to_string(), Binding::Function(params, block));
+    }
+
+    /// Looks up a variable in this scope only.
+    fn get_own(&self, name: &str) -> Option<ValueType> {
+        match self.0.values.lock().unwrap().get(name)? {
+            Binding::Value(value) => Some(value.clone()),
+            Binding::Function(params, block) => Some(ValueType::Lambda(params.clone(), block.clone(), self.clone())),
+        }
+    }
+
+    /// Looks up a variable in this scope and then in the outer scopes. Qualified names like
+    /// `common.count` are looked up in the module of the namespace.
+    pub fn get(&self, name: &str) -> Option<ValueType> {
+        if let Some((namespace, name)) = name.rsplit_once('.') {
+            return self.module(namespace)?.get_own(name);
+        }
+
+        let mut env = self;
+        loop {
+            if let Some(value) = env.get_own(name) {
+                return Some(value);
+            }
+            env = env.0.parent.as_ref()?;
+        }
+    }
+
+    /// Updates the innermost variable with the given name, returns false if there is none.
+    pub fn assign(&self, name: &str, value: ValueType) -> bool {
+        if let Some((namespace, name)) = name.rsplit_once('.') {
+            let module = match self.module(namespace) {
+                Some(module) => module,
+                None => return false,
+            };
+            return match module.0.values.lock().unwrap().get_mut(name) {
+                Some(v) => {
+                    *v = Binding::Value(value);
+                    true
+                }
+                None => false,
+            };
+        }
+
+        let mut env = self;
+        loop {
+            if let Some(v) = env.0.values.lock().unwrap().get_mut(name) {
+                *v = Binding::Value(value);
+                return true;
+            }
+            env = match &env.0.parent {
+                Some(parent) => parent,
+                None => return false,
+            };
+        }
+    }
+
+    /// The scope of the module imported under the given namespace, nested namespaces are
+    /// separated by dots.
+    fn module(&self, namespace: &str) -> Option<Environment> {
+        match self.get(namespace)? {
+            ValueType::Module(module) => Some(module),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self { Environment::new() }
+}
+
+/// Environments are compared by identity, two lambdas are only equal if they share their scope.
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool { Arc::ptr_eq(&self.0, &other.0) }
+}
+
+// scopes can contain lambdas that refer back to them, so the contents aren't printed
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Environment({:p})", Arc::as_ptr(&self.0))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes() {
+        let global = Environment::new();
+        global.define("a", ValueType::Number(1.0));
+
+        let child = global.child();
+        child.define("b", ValueType::Number(2.0));
+        assert_eq!(child.get("a"), Some(ValueType::Number(1.0)));
+        assert_eq!(global.get("b"), None);
+
+        // assignments update the scope that defines the variable
+        assert!(child.assign("a", ValueType::Number(3.0)));
+        assert_eq!(global.get("a"), Some(ValueType::Number(3.0)));
+        assert!(!child.assign("c", ValueType::Void));
+
+        child.define("a", ValueType::Bool(true));
+        assert_eq!(child.get("a"), Some(ValueType::Bool(true)));
+        assert_eq!(global.get("a"), Some(ValueType::Number(3.0)));
+    }
+
+    #[test]
+    fn test_shared_by_reference() {
+        let global = Environment::new();
+        let first = global.child();
+        let second = global.child();
+        global.define("count", ValueType::Number(0.0));
+
+        // both children see changes made through the other
+        first.assign("count", ValueType::Number(1.0));
+        assert_eq!(second.get("count"), Some(ValueType::Number(1.0)));
+
+        assert_eq!(first.clone(), first);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_functions() {
+        let global = Environment::new();
+        let scope = global.child();
+        scope.define_function("f", vec!["x".to_string()], Block::new());
+
+        // the lambda captures the scope that declares the function, without the scope holding on
+        // to itself
+        assert!(matches!(scope.get("f"), Some(ValueType::Lambda(params, _, env)) if params == ["x"] && env == scope));
+        assert_eq!(Arc::strong_count(&scope.0), 1);
+        assert_eq!(global.get("f"), None);
+
+        assert!(scope.assign("f", ValueType::Number(1.0)));
+        assert_eq!(scope.get("f"), Some(ValueType::Number(1.0)));
+    }
+
+    #[test]
+    fn test_modules() {
+        let global = Environment::new();
+        let module = global.child();
+        global.define("common", ValueType::Module(module.clone()));
+        module.define("count", ValueType::Number(0.0));
+
+        // the module is shared, not copied
+        assert!(module.assign("count", ValueType::Number(1.0)));
+        assert_eq!(global.get("common.count"), Some(ValueType::Number(1.0)));
+        assert!(global.assign("common.count", ValueType::Number(2.0)));
+        assert_eq!(module.get("count"), Some(ValueType::Number(2.0)));
+
+        module.define_function("reset", vec![], Block::new());
+        assert!(matches!(global.get("common.reset"), Some(ValueType::Lambda(_, _, env)) if env == module));
+        assert_eq!(global.get("common.missing"), None);
+        assert_eq!(global.get("count"), None);
+        assert!(!global.assign("other.count", ValueType::Void));
+    }
+}
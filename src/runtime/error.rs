@@ -0,0 +1,118 @@
+use std::fmt;
+
+use crate::*;
+
+/// The location of a statement in a script, the line is 0 for statements that were generated at
+/// runtime.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Span {
+    pub file: Option<Arc<str>>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn is_known(&self) -> bool { self.line != 0 }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}:{}", file, self.line, self.column),
+            None => write!(f, "{}:{}", self.line, self.column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    /// An operator or statement received values of the wrong type.
+    TypeMismatch(String),
+    DivisionByZero,
+    UndefinedVariable(String),
+    /// Raised by a built-in or user defined function.
+    Function(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub span: Span,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind) -> Self { RuntimeError { kind, span: Span::default() } }
+
+    pub fn type_mismatch(message: String) -> Self { RuntimeError::new(RuntimeErrorKind::TypeMismatch(message)) }
+
+    /// Attaches the location of the statement that failed, errors keep the innermost location.
+    pub fn at(mut self, span: &Span) -> Self {
+        if !self.span.is_known() { self.span = span.clone(); }
+        self
+    }
+}
+
+impl From<anyhow::Error> for RuntimeError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<RuntimeError>() {
+            Ok(err) => err,
+            Err(err) => RuntimeError::new(RuntimeErrorKind::Function(err.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::TypeMismatch(message) => write!(f, "{}", message),
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::UndefinedVariable(name) => write!(f, "variable '{}' does not exist", name),
+            RuntimeErrorKind::Function(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.span.is_known() {
+            write!(f, "{}: {}", self.span, self.kind)
+        } else {
+            write!(f, "{}", self.kind)
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// What happens after a runtime error was logged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorPolicy {
+    Exit,
+    Continue,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let err = RuntimeError::type_mismatch("cannot subtract string from number".to_string())
+            .at(&Span { file: Some("script.m2".into()), line: 12, column: 5 })
+            .at(&Span { file: Some("script.m2".into()), line: 1, column: 1 });
+        assert_eq!(err.to_string(), "script.m2:12:5: cannot subtract string from number");
+
+        assert_eq!(RuntimeError::new(RuntimeErrorKind::DivisionByZero).to_string(), "division by zero");
+    }
+
+    #[test]
+    fn test_from_anyhow() {
+        let err = RuntimeError::new(RuntimeErrorKind::DivisionByZero)
+            .at(&Span { file: None, line: 3, column: 1 });
+        assert_eq!(RuntimeError::from(anyhow::Error::new(err.clone())), err);
+
+        assert_eq!(RuntimeError::from(anyhow!("function 'foo' not found")).kind,
+                   RuntimeErrorKind::Function("function 'foo' not found".to_string()));
+    }
+}
@@ -1,4 +1,5 @@
 use std::borrow::BorrowMut;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::Formatter;
 
@@ -7,22 +8,60 @@ use messaging::*;
 use crate::*;
 
 use super::builtin_functions::evaluate_builtin;
-use super::builtin_functions::throw_error;
-
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub(crate) struct KeyActionCondition {
-    pub(crate) window_class_name: Option<String>,
-}
 
 #[derive(Clone, Debug)]
 pub enum ValueType {
     Bool(bool),
     String(String),
-    Lambda(Vec<String>, Block, GuardedVarMap),
+    Lambda(Vec<String>, Block, Environment),
     Number(f64),
+    List(GuardedList),
+    Map(GuardedMap),
+    /// A handle to a task started by `spawn`.
+    Task(Arc<ScriptTask>),
+    Channel(Arc<ScriptChannel>),
+    /// A handle to a mapping, returned by mapping definitions and `map_key`.
+    Mapping(usize),
+    /// A handle to a timer started by `set_timeout` or `set_interval`.
+    Timer(usize),
+    /// The variables of a file imported under a namespace, shared with the file's lambdas.
+    Module(Environment),
     Void,
 }
 
+/// Lists are shared by reference, changes are visible to every variable that holds the list.
+pub type GuardedList = Arc<Mutex<Vec<ValueType>>>;
+
+/// Maps are shared by reference like lists, their keys are kept in sorted order.
+pub type GuardedMap = Arc<Mutex<BTreeMap<String, ValueType>>>;
+
+/// Formats a value that is nested in a list or map, strings are quoted to tell them apart.
+fn fmt_nested(value: &ValueType) -> String {
+    match value {
+        ValueType::String(v) => format!("\"{}\"", v),
+        v => v.to_string(),
+    }
+}
+
+impl ValueType {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ValueType::Bool(_) => "bool",
+            ValueType::String(_) => "string",
+            ValueType::Lambda(_, _, _) => "function",
+            ValueType::Number(_) => "number",
+            ValueType::List(_) => "list",
+            ValueType::Map(_) => "map",
+            ValueType::Task(_) => "task",
+            ValueType::Channel(_) => "channel",
+            ValueType::Mapping(_) => "mapping",
+            ValueType::Timer(_) => "timer",
+            ValueType::Module(_) => "module",
+            ValueType::Void => "void",
+        }
+    }
+}
+
 impl PartialEq for ValueType {
     fn eq(&self, other: &Self) -> bool {
         use ValueType::*;
@@ -30,6 +69,14 @@ impl PartialEq for ValueType {
             (String(l), String(r)) => l == r,
             (Bool(l), Bool(r)) => l == r,
             (Number(l), Number(r)) => l == r,
+            (List(l), List(r)) => arc_mutexes_are_equal(l, r),
+            (Map(l), Map(r)) => arc_mutexes_are_equal(l, r),
+            (Task(l), Task(r)) => Arc::ptr_eq(l, r),
+            (Channel(l), Channel(r)) => Arc::ptr_eq(l, r),
+            (Mapping(l), Mapping(r)) => l == r,
+            (Timer(l), Timer(r)) => l == r,
+            (Module(l), Module(r)) => l == r,
+            (Void, Void) => true,
             (_, _) => false,
         }
     }
@@ -42,218 +89,262 @@ impl fmt::Display for ValueType {
             ValueType::String(v) => write!(f, "{}", v),
             ValueType::Number(v) => write!(f, "{}", v),
             ValueType::Lambda(_, _, _) => write!(f, "Lambda"),
+            ValueType::Task(_) => write!(f, "Task"),
+            ValueType::Channel(_) => write!(f, "Channel"),
+            ValueType::Mapping(_) => write!(f, "Mapping"),
+            ValueType::Timer(_) => write!(f, "Timer"),
+            ValueType::Module(_) => write!(f, "Module"),
+            ValueType::List(v) => {
+                let items: Vec<String> = v.lock().unwrap().iter().map(fmt_nested).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            ValueType::Map(v) => {
+                let items: Vec<String> = v.lock().unwrap().iter()
+                    .map(|(key, value)| format!("\"{}\": {}", key, fmt_nested(value)))
+                    .collect();
+                write!(f, "{{{}}}", items.join(", "))
+            }
             ValueType::Void => write!(f, "Void"),
         }
     }
 }
 
-#[derive(Debug)]
-pub struct VarMap {
-    pub(crate) scope_values: HashMap<String, ValueType>,
-    pub(crate) parent: Option<GuardedVarMap>,
-}
-
-impl VarMap {
-    pub fn new(parent: Option<GuardedVarMap>) -> Self {
-        VarMap { scope_values: Default::default(), parent }
-    }
-}
-
-impl PartialEq for VarMap {
-    fn eq(&self, other: &Self) -> bool {
-        self.scope_values == other.scope_values &&
-            match (&self.parent, &other.parent) {
-                (None, None) => true,
-                (Some(l), Some(r)) => arc_mutexes_are_equal(&*l, &*r),
-                (_, _) => false,
-            }
-    }
-}
-
-pub type GuardedVarMap = Arc<Mutex<VarMap>>;
-
-
 #[async_recursion]
-pub(crate) async fn eval_expr<'a>(expr: &Expr, var_map: &GuardedVarMap, amb: &mut Ambient<'_>) -> ValueType {
+pub(crate) async fn eval_expr<'a>(expr: &Expr, var_map: &Environment, amb: &mut Ambient<'_>) -> Result<ValueType, RuntimeError> {
     use ValueType::*;
-    match expr {
+    let value = match expr {
         Expr::Eq(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
-                (Bool(left), Bool(right)) => Bool(left == right),
-                (String(left), String(right)) => Bool(left == right),
-                (Number(left), Number(right)) => Bool(left == right),
-                _ => Bool(false),
-            }
+            // values of different types are never equal
+            let (left, right) = (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?);
+            Bool(left == right)
         }
         Expr::Neq(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
-                (Bool(left), Bool(right)) => Bool(left != right),
-                (String(left), String(right)) => Bool(left != right),
-                (Number(left), Number(right)) => Bool(left != right),
-                _ => Bool(true),
-            }
+            // values of different types are never equal
+            let (left, right) = (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?);
+            Bool(left != right)
         }
         Expr::LT(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
                 (Bool(left), Bool(right)) => Bool(left < right),
                 (String(left), String(right)) => Bool(left < right),
                 (Number(left), Number(right)) => Bool(left < right),
-                _ => Bool(false),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot compare {} to {}", left.type_name(), right.type_name()))),
             }
         }
         Expr::GT(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
                 (Bool(left), Bool(right)) => Bool(left > right),
                 (String(left), String(right)) => Bool(left > right),
                 (Number(left), Number(right)) => Bool(left > right),
-                _ => Bool(false),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot compare {} to {}", left.type_name(), right.type_name()))),
+            }
+        }
+        Expr::LE(left, right) => {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
+                (Bool(left), Bool(right)) => Bool(left <= right),
+                (String(left), String(right)) => Bool(left <= right),
+                (Number(left), Number(right)) => Bool(left <= right),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot compare {} to {}", left.type_name(), right.type_name()))),
+            }
+        }
+        Expr::GE(left, right) => {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
+                (Bool(left), Bool(right)) => Bool(left >= right),
+                (String(left), String(right)) => Bool(left >= right),
+                (Number(left), Number(right)) => Bool(left >= right),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot compare {} to {}", left.type_name(), right.type_name()))),
             }
         }
         Expr::Add(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
                 (Number(left), Number(right)) => Number(left + right),
                 (String(left), right) => String(format!("{}{}", left, right)),
                 (left, String(right)) => String(format!("{}{}", left, right)),
-                _ => panic!("cannot add unsupported types"),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot add {} to {}", right.type_name(), left.type_name()))),
             }
         }
         Expr::Sub(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
                 (Number(left), Number(right)) => Number(left - right),
-                _ => panic!("cannot subtract unsupported types"),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot subtract {} from {}", right.type_name(), left.type_name()))),
             }
         }
         Expr::Mul(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
                 (Number(left), Number(right)) => Number(left * right),
-                _ => panic!("cannot multiply unsupported types"),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot multiply {} by {}", left.type_name(), right.type_name()))),
             }
         }
         Expr::Div(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
                 (Number(left), Number(right)) => {
-                    if right == 0.0 { panic!("error: division by zero"); }
+                    if right == 0.0 { return Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero)); }
                     Number(left / right)
                 }
-                _ => panic!("cannot multiply unsupported types"),
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot divide {} by {}", left.type_name(), right.type_name()))),
+            }
+        }
+        Expr::Mod(left, right) => {
+            match (eval_expr(left, var_map, amb).await?, eval_expr(right, var_map, amb).await?) {
+                (Number(left), Number(right)) => {
+                    if right == 0.0 { return Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero)); }
+                    Number(left % right)
+                }
+                (left, right) => return Err(RuntimeError::type_mismatch(format!("cannot take the remainder of {} divided by {}", left.type_name(), right.type_name()))),
             }
         }
         Expr::Neg(expr) => {
-            match eval_expr(expr, var_map, amb).await {
+            match eval_expr(expr, var_map, amb).await? {
                 Bool(val) => { Bool(!val) }
-                _ => panic!("cannot negate unsupported type"),
+                val => return Err(RuntimeError::type_mismatch(format!("cannot negate {}", val.type_name()))),
+            }
+        }
+        Expr::Minus(expr) => {
+            match eval_expr(expr, var_map, amb).await? {
+                Number(val) => Number(-val),
+                val => return Err(RuntimeError::type_mismatch(format!("cannot negate {}", val.type_name()))),
             }
         }
         Expr::And(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
-                (Bool(left), Bool(right)) => Bool(left == right),
-                _ => panic!("cannot perform \"and\" operation on unsupported types"),
+            // the right side is only evaluated if needed
+            match eval_expr(left, var_map, amb).await? {
+                Bool(false) => Bool(false),
+                Bool(true) => match eval_expr(right, var_map, amb).await? {
+                    Bool(right) => Bool(right),
+                    right => return Err(RuntimeError::type_mismatch(format!("'&&' expects booleans, got {}", right.type_name()))),
+                },
+                left => return Err(RuntimeError::type_mismatch(format!("'&&' expects booleans, got {}", left.type_name()))),
             }
         }
         Expr::Or(left, right) => {
-            match (eval_expr(left, var_map, amb).await, eval_expr(right, var_map, amb).await) {
-                (Bool(left), Bool(right)) => Bool(left || right),
-                _ => panic!("cannot perform \"or\" operation on unsupported types"),
+            match eval_expr(left, var_map, amb).await? {
+                Bool(true) => Bool(true),
+                Bool(false) => match eval_expr(right, var_map, amb).await? {
+                    Bool(right) => Bool(right),
+                    right => return Err(RuntimeError::type_mismatch(format!("'||' expects booleans, got {}", right.type_name()))),
+                },
+                left => return Err(RuntimeError::type_mismatch(format!("'||' expects booleans, got {}", left.type_name()))),
             }
         }
         Expr::Init(var_name, value) => {
-            let value = eval_expr(value, var_map, amb).await;
+            let value = eval_expr(value, var_map, amb).await?;
 
-            var_map.lock().unwrap().scope_values.insert(var_name.clone(), value);
-            return ValueType::Void;
+            var_map.define(var_name, value);
+            return Ok(ValueType::Void);
         }
         Expr::Assign(var_name, value) => {
-            let value = eval_expr(value, var_map, amb).await;
+            let value = eval_expr(value, var_map, amb).await?;
 
-            let mut map = var_map.clone();
-            loop {
-                let tmp;
-                let mut map_guard = map.lock().unwrap();
-                match map_guard.scope_values.get_mut(var_name) {
-                    Some(v) => {
-                        *v = value;
-                        break;
-                    }
-                    None => match &map_guard.parent {
-                        Some(parent) => tmp = parent.clone(),
-                        None => { panic!("variable '{}' does not exist", var_name); }
-                    }
-                }
-                drop(map_guard);
-                map = tmp;
+            if !var_map.assign(var_name, value) {
+                return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(var_name.clone())));
             }
             ValueType::Void
         }
         Expr::KeyMapping(mappings) => {
-            for mapping in mappings {
-                let mapping = mapping.clone();
+            // the id is returned as a handle that can be used to remove the mapping again
+            return add_key_mappings(mappings, None, None, var_map, amb).await;
+        }
+        Expr::GuardedKeyMapping(mappings, guard) => {
+            return add_key_mappings(mappings, Some(guard), None, var_map, amb).await;
+        }
+        Expr::RepeatKeyMapping(mapping, repeat) => {
+            return match mapping.deref() {
+                Expr::KeyMapping(mappings) => add_key_mappings(mappings, None, Some(*repeat), var_map, amb).await,
+                Expr::GuardedKeyMapping(mappings, guard) => add_key_mappings(mappings, Some(guard), Some(*repeat), var_map, amb).await,
+                _ => unreachable!(),
+            };
+        }
+        Expr::TapHoldMapping(key, tap_hold) => {
+            let id = next_mapping_id();
+            amb.message_tx.borrow_mut().as_ref().unwrap()
+                .send(ExecutionMessage::AddTapHoldMapping(amb.window_cycle_token, id, *key, tap_hold.clone())).await
+                .unwrap();
 
-                amb.message_tx.borrow_mut().as_ref().unwrap()
-                    .send(ExecutionMessage::AddMapping(amb.window_cycle_token, mapping.from, mapping.to, var_map.clone())).await
-                    .unwrap();
+            return Ok(ValueType::Mapping(id));
+        }
+        Expr::ChordMapping(keys, down, up) => {
+            let id = next_mapping_id();
+            let mapping = ChordMapping { id, keys: keys.clone(), down: down.clone(), up: up.clone(), var_map: var_map.clone() };
+            amb.message_tx.borrow_mut().as_ref().unwrap()
+                .send(ExecutionMessage::AddChordMapping(amb.window_cycle_token, mapping)).await
+                .unwrap();
+
+            return Ok(ValueType::Mapping(id));
+        }
+        Expr::SequenceMapping(steps, block) => {
+            let id = next_mapping_id();
+            let mapping = SequenceMapping { id, steps: steps.clone(), block: block.clone(), var_map: var_map.clone() };
+            amb.message_tx.borrow_mut().as_ref().unwrap()
+                .send(ExecutionMessage::AddSequenceMapping(amb.window_cycle_token, mapping)).await
+                .unwrap();
+
+            return Ok(ValueType::Mapping(id));
+        }
+        Expr::RelMapping(trigger, guard, block) => {
+            if let Some(layer) = &amb.layer {
+                return Err(RuntimeError::new(RuntimeErrorKind::Function(format!("mouse and wheel mappings can't be added to layer '{}'", layer))));
             }
 
-            return ValueType::Void;
-        }
-        Expr::Name(var_name) => {
-            let mut value = None;
-            let mut map = var_map.clone();
+            let id = next_mapping_id();
+            let target = MappingTarget { id, guard: guard.as_deref().cloned(), window: amb.window.clone(), repeat: None, block: block.clone(), var_map: var_map.clone() };
+            amb.message_tx.borrow_mut().as_ref().unwrap()
+                .send(ExecutionMessage::AddRelMapping(amb.window_cycle_token, *trigger, target)).await
+                .unwrap();
 
-            loop {
-                let tmp;
-                let map_guard = map.lock().unwrap();
-                match map_guard.scope_values.get(var_name) {
-                    Some(v) => {
-                        value = Some(v.clone());
-                        break;
-                    }
-                    None => match &map_guard.parent {
-                        Some(parent) => tmp = parent.clone(),
-                        None => { break; }
-                    }
-                }
-                drop(map_guard);
-                map = tmp;
+            return Ok(ValueType::Mapping(id));
+        }
+        Expr::Name(var_name) => var_map.get(var_name).unwrap_or(ValueType::Void),
+        Expr::Value(value) => {
+            return Ok(value.clone());
+        }
+        Expr::List(items) => {
+            let mut values = vec![];
+            for item in items {
+                values.push(eval_expr(item, var_map, amb).await?);
             }
-
-            match value {
-                Some(value) => value,
-                None => ValueType::Void,
+            List(GuardedList::new(Mutex::new(values)))
+        }
+        Expr::Map(items) => {
+            let mut values = BTreeMap::new();
+            for (key, value) in items {
+                values.insert(key.clone(), eval_expr(value, var_map, amb).await?);
             }
+            Map(GuardedMap::new(Mutex::new(values)))
         }
-        Expr::Value(value) => {
-            return value.clone();
+        Expr::Index(value, idx) => {
+            match (eval_expr(value, var_map, amb).await?, eval_expr(idx, var_map, amb).await?) {
+                (List(list), Number(idx)) => {
+                    // indices outside of the list result in void
+                    if idx < 0.0 || idx.fract() != 0.0 { return Ok(Void); }
+                    list.lock().unwrap().get(idx as usize).cloned().unwrap_or(Void)
+                }
+                // missing keys result in void
+                (Map(map), String(key)) => map.lock().unwrap().get(&key).cloned().unwrap_or(Void),
+                (value, idx) => return Err(RuntimeError::type_mismatch(format!("cannot index {} with {}", value.type_name(), idx.type_name()))),
+            }
         }
         Expr::Lambda(params, block) => {
-            let lambda_var_map = GuardedVarMap::new(Mutex::new(VarMap::new(Some(var_map.clone()))));
-            return ValueType::Lambda(params.clone(), block.clone(), lambda_var_map);
+            // the defining scope is captured by reference
+            return Ok(ValueType::Lambda(params.clone(), block.clone(), var_map.clone()));
         }
         Expr::KeyAction(action) => {
             amb.ev_writer_tx.send(action.to_input_ev()).await.unwrap();
             amb.ev_writer_tx.send(SYN_REPORT.clone()).await.unwrap();
 
-            return ValueType::Void;
+            return Ok(ValueType::Void);
         }
         // Expr::EatKeyAction(action) => {
         //     match &amb.message_tx {
         //         Some(tx) => { tx.send(ExecutionMessage::EatEv(action.clone())).await.unwrap(); }
         //         None => panic!("need message tx"),
         //     }
-        //     return ValueType::Void;
+        //     return Ok(ValueType::Void);
         // }
         Expr::SleepAction(duration) => {
             tokio::time::sleep(*duration).await;
-            return ValueType::Void;
-        }
-        Expr::FunctionCall(name, args) => {
-            match evaluate_builtin(name, args, var_map, amb).await {
-                Ok(v) => v,
-                Err(err) => {
-                    throw_error(err, 1, amb).await;
-                    ValueType::Void
-                }
-            }
+            return Ok(ValueType::Void);
         }
+        Expr::FunctionCall(name, args) => evaluate_builtin(name, args, var_map, amb).await?,
         Expr::ReleaseRestoreModifiers(from_flags, to_flags, to_type) => {
             let actual_state = &amb.modifier_state;
 
@@ -274,28 +365,52 @@ pub(crate) async fn eval_expr<'a>(expr: &Expr, var_map: &GuardedVarMap, amb: &mu
                 }
             };
 
-            if from_flags.ctrl && !to_flags.ctrl {
-                release_or_restore_modifier(&actual_state.left_ctrl, &*KEY_LEFT_CTRL);
-                release_or_restore_modifier(&actual_state.right_ctrl, &*KEY_RIGHT_CTRL);
-            }
-            if from_flags.shift && !to_flags.shift {
-                release_or_restore_modifier(&actual_state.left_shift, &*KEY_LEFT_SHIFT);
-                release_or_restore_modifier(&actual_state.right_shift, &*KEY_RIGHT_SHIFT);
-            }
-            if from_flags.alt && !to_flags.alt {
-                release_or_restore_modifier(&actual_state.left_alt, &*KEY_LEFT_ALT);
-                release_or_restore_modifier(&actual_state.right_alt, &*KEY_RIGHT_ALT);
-            }
-            if from_flags.meta && !to_flags.meta {
-                release_or_restore_modifier(&actual_state.left_meta, &*KEY_LEFT_META);
-                release_or_restore_modifier(&actual_state.right_meta, &*KEY_RIGHT_META);
+            // wildcard triggers release all modifiers that aren't part of the output, unless the output
+            // passes them through
+            let release_unmatched = from_flags.wildcard && !to_flags.wildcard;
+
+            // side-specific target flags only keep the modifier on the requested side
+            let modifier_pairs = [
+                (Modifier::Ctrl, actual_state.left_ctrl, actual_state.right_ctrl),
+                (Modifier::Shift, actual_state.left_shift, actual_state.right_shift),
+                (Modifier::Alt, actual_state.left_alt, actual_state.right_alt),
+                (Modifier::Meta, actual_state.left_meta, actual_state.right_meta),
+            ];
+            for (modifier, is_left_down, is_right_down) in modifier_pairs.iter() {
+                if !from_flags.is_set(*modifier) && !release_unmatched { continue; }
+                if !to_flags.accepts(*modifier, ModifierSide::Left) {
+                    release_or_restore_modifier(is_left_down, &modifier.key(ModifierSide::Left));
+                }
+                if !to_flags.accepts(*modifier, ModifierSide::Right) {
+                    release_or_restore_modifier(is_right_down, &modifier.key(ModifierSide::Right));
+                }
             }
 
             // TODO eat keys we just released, un-eat keys we just restored
 
-            return ValueType::Void;
+            return Ok(ValueType::Void);
         }
+    };
+
+    Ok(value)
+}
+
+/// Adds the mappings of a mapping expression, software repeat is tracked by the target of the
+/// key press.
+async fn add_key_mappings(mappings: &[KeyMapping], guard: Option<&Expr>, repeat: Option<SoftwareRepeat>,
+                          var_map: &Environment, amb: &mut Ambient<'_>) -> Result<ValueType, RuntimeError> {
+    let id = next_mapping_id();
+    for mapping in mappings {
+        let mapping = mapping.clone();
+        let repeat = repeat.filter(|_| mapping.from.value == TYPE_DOWN);
+        let target = MappingTarget { id, guard: guard.cloned(), window: amb.window.clone(), repeat, block: mapping.to, var_map: var_map.clone() };
+
+        amb.message_tx.borrow_mut().as_ref().unwrap()
+            .send(ExecutionMessage::AddMapping(amb.window_cycle_token, amb.layer.clone(), mapping.from, target)).await
+            .unwrap();
     }
+
+    Ok(ValueType::Mapping(id))
 }
 
 pub type SleepSender = tokio::sync::mpsc::Sender<Block>;
@@ -305,90 +420,228 @@ pub struct Ambient<'a> {
     pub message_tx: Option<&'a mut ExecutionMessageSender>,
     pub window_cycle_token: usize,
     pub modifier_state: &'a KeyModifierState,
+    /// the layer that key mappings are added to, the base mappings if unset
+    pub layer: Option<String>,
+    /// the windows that key mappings are scoped to, active in every window if unset
+    pub window: Option<WindowCondition>,
+    /// set while a mouse or wheel mapping runs, `send_rel` leaves ending the frame to the mapping so
+    /// that the axes it sends are reported together
+    pub rel_frame: bool,
 }
 
 pub enum BlockRet {
     None,
     Continue,
+    Break,
     Return(ValueType),
 }
 
 #[async_recursion]
-pub async fn eval_block<'a>(block: &Block, var_map: &GuardedVarMap, amb: &mut Ambient<'a>) -> BlockRet {
-    let mut var_map = GuardedVarMap::new(Mutex::new(VarMap::new(Some(var_map.clone()))));
-
-    'outer: for stmt in &block.statements {
-        match stmt {
-            Stmt::Expr(expr) => { eval_expr(expr, &var_map, amb).await; }
-            Stmt::Block(nested_block) => {
-                let ret = eval_block(nested_block, &mut var_map, amb).await;
-                match ret {
-                    BlockRet::None => {}
-                    _ => return ret,
-                };
-            }
-            Stmt::If(if_else_if_pairs, else_pair) => {
-                for (expr, block) in if_else_if_pairs {
-                    if eval_expr(expr, &mut var_map, amb).await == ValueType::Bool(true) {
-                        let ret = eval_block(block, &mut var_map, amb).await;
-                        match ret {
-                            BlockRet::None => {}
-                            _ => return ret,
-                        };
-                        continue 'outer;
-                    }
-                }
-                if let Some(block) = else_pair {
-                    let ret = eval_block(block, &mut var_map, amb).await;
-                    match ret {
-                        BlockRet::None => {}
-                        _ => return ret,
-                    };
+pub async fn eval_block<'a>(block: &Block, var_map: &Environment, amb: &mut Ambient<'a>) -> Result<BlockRet, RuntimeError> {
+    eval_statements(block, &var_map.child(), amb).await
+}
+
+/// Evaluates the statements of a block in the given scope rather than in a new one.
+async fn eval_statements<'a>(block: &Block, var_map: &Environment, amb: &mut Ambient<'a>) -> Result<BlockRet, RuntimeError> {
+    // function declarations are hoisted so they can be called before their definition and from
+    // each other
+    for stmt in &block.statements {
+        if let Stmt::Function(name, params, body) = stmt {
+            var_map.define_function(name, params.clone(), body.clone());
+        }
+    }
+
+    for (idx, stmt) in block.statements.iter().enumerate() {
+        // errors point at the innermost statement that failed
+        let ret = eval_stmt(stmt, var_map, amb).await
+            .map_err(|err| err.at(&block.span(idx)))?;
+
+        match ret {
+            BlockRet::None => {}
+            _ => return Ok(ret),
+        };
+    }
+
+    Ok(BlockRet::None)
+}
+
+#[async_recursion]
+async fn eval_stmt<'a>(stmt: &Stmt, var_map: &Environment, amb: &mut Ambient<'a>) -> Result<BlockRet, RuntimeError> {
+    match stmt {
+        Stmt::Expr(expr) => { eval_expr(expr, var_map, amb).await?; }
+        Stmt::Block(nested_block) => {
+            return eval_block(nested_block, var_map, amb).await;
+        }
+        Stmt::If(if_else_if_pairs, else_pair) => {
+            for (expr, block) in if_else_if_pairs {
+                if eval_expr(expr, var_map, amb).await? == ValueType::Bool(true) {
+                    return eval_block(block, var_map, amb).await;
                 }
             }
-            Stmt::For(init_expr, termination_expr, advance_expr, block) => {
-                eval_expr(init_expr, &var_map, amb).await;
-
-                loop {
-                    let should_continue = match eval_expr(termination_expr, &var_map, amb).await {
-                        ValueType::Bool(v) => v,
-                        _ => panic!("termination condition in for loop needs to return a boolean"),
-                    };
-                    if !should_continue { break; }
-
-                    let ret = eval_block(block, &mut var_map, amb).await;
-                    match ret {
-                        BlockRet::Return(_) => return ret,
-                        _ => {}
-                    };
-
-                    eval_expr(advance_expr, &var_map, amb).await;
-                }
+            if let Some(block) = else_pair {
+                return eval_block(block, var_map, amb).await;
             }
-            Stmt::Return(expr) => {
-                return BlockRet::Return(eval_expr(expr, &var_map, amb).await);
+        }
+        Stmt::For(init_expr, termination_expr, advance_expr, block) => {
+            // the loop variable is only visible inside of the loop
+            let var_map = &var_map.child();
+            eval_expr(init_expr, var_map, amb).await?;
+
+            loop {
+                let should_continue = match eval_expr(termination_expr, var_map, amb).await? {
+                    ValueType::Bool(v) => v,
+                    v => return Err(RuntimeError::type_mismatch(format!("termination condition in for loop needs to return a boolean, got {}", v.type_name()))),
+                };
+                if !should_continue { break; }
+
+                let ret = eval_block(block, var_map, amb).await?;
+                match ret {
+                    BlockRet::Return(_) => return Ok(ret),
+                    BlockRet::Break => break,
+                    _ => {}
+                };
+
+                eval_expr(advance_expr, var_map, amb).await?;
+            }
+        }
+        Stmt::ForIn(item_name, list_expr, block) => {
+            let items = match eval_expr(list_expr, var_map, amb).await? {
+                ValueType::List(list) => list.lock().unwrap().clone(),
+                ValueType::Map(map) => map.lock().unwrap().keys().cloned().map(ValueType::String).collect(),
+                v => return Err(RuntimeError::type_mismatch(format!("for loops can only iterate over lists and maps, got {}", v.type_name()))),
+            };
+
+            for item in items {
+                // every iteration gets a fresh scope for the loop variable
+                let item_var_map = var_map.child();
+                item_var_map.define(item_name, item);
+
+                let ret = eval_block(block, &item_var_map, amb).await?;
+                match ret {
+                    BlockRet::Return(_) => return Ok(ret),
+                    BlockRet::Break => break,
+                    _ => {}
+                };
             }
-            Stmt::Continue => {
-                return BlockRet::Continue;
+        }
+        Stmt::While(termination_expr, block) => {
+            loop {
+                let should_continue = match eval_expr(termination_expr, var_map, amb).await? {
+                    ValueType::Bool(v) => v,
+                    v => return Err(RuntimeError::type_mismatch(format!("condition in while loop needs to return a boolean, got {}", v.type_name()))),
+                };
+                if !should_continue { break; }
+
+                let ret = eval_block(block, var_map, amb).await?;
+                match ret {
+                    BlockRet::Return(_) => return Ok(ret),
+                    BlockRet::Break => break,
+                    _ => {}
+                };
             }
         }
+        Stmt::WhenWindow(condition, nested_block) => {
+            // mappings defined in the block are only active while a matching window is focused
+            let outer_window = amb.window.replace(condition.clone());
+            let ret = eval_block(nested_block, var_map, amb).await;
+            amb.window = outer_window;
+            return ret;
+        }
+        // already defined when the block started
+        Stmt::Function(_, _, _) => {}
+        Stmt::Import(path, _) => {
+            return Err(RuntimeError::new(RuntimeErrorKind::Function(format!("the import of '{}' was not resolved", path))));
+        }
+        Stmt::Namespace(namespace, nested_block) => {
+            // the module's variables are looked up as 'namespace.name' when they're used, so changes
+            // made by the module's lambdas are visible. The namespace is bound first so that repeated
+            // imports within the module can refer to it.
+            let module_var_map = var_map.child();
+            var_map.define(namespace, ValueType::Module(module_var_map.clone()));
+            eval_statements(nested_block, &module_var_map, amb).await?;
+        }
+        Stmt::Return(expr) => {
+            return Ok(BlockRet::Return(eval_expr(expr, var_map, amb).await?));
+        }
+        Stmt::Continue => {
+            return Ok(BlockRet::Continue);
+        }
+        Stmt::Break => {
+            return Ok(BlockRet::Break);
+        }
+    }
+
+    Ok(BlockRet::None)
+}
+
+/// Returns the target that handles a key event, presses pick the first applying target and their
+/// repeat and release events go to the same target without checking the conditions again.
+pub async fn handling_target<'a, 't>(targets: &'t [MappingTarget], window: Option<&ActiveWindowInfo>, action: &KeyAction, pressed: &PressedTargets,
+                                     amb: &mut Ambient<'a>) -> Result<Option<&'t MappingTarget>, RuntimeError> {
+    if action.value == TYPE_DOWN {
+        let target = applying_target(targets, window, amb).await?;
+        pressed.press(action.key, PressedTarget {
+            handled_by: target.map(|v| v.id),
+            considered: targets.iter().map(|v| v.id).collect(),
+        });
+        return Ok(target);
+    }
+
+    match pressed.pinned(action.key, action.value) {
+        Some(pin) => {
+            if let Some(target) = targets.iter().find(|v| Some(v.id) == pin.handled_by) { return Ok(Some(target)); }
+            // mappings that only define this event weren't involved in the press
+            let targets = targets.iter().filter(|v| !pin.considered.contains(&v.id)).collect();
+            first_applying_target(targets, window, amb).await
+        }
+        None => applying_target(targets, window, amb).await,
     }
+}
 
-    BlockRet::None
+/// Returns the first target that is active in the window and whose guard holds.
+pub async fn applying_target<'a, 't>(targets: &'t [MappingTarget], window: Option<&ActiveWindowInfo>, amb: &mut Ambient<'a>) -> Result<Option<&'t MappingTarget>, RuntimeError> {
+    first_applying_target(targets.iter().collect(), window, amb).await
+}
+
+async fn first_applying_target<'a, 't>(targets: Vec<&'t MappingTarget>, window: Option<&ActiveWindowInfo>,
+                                       amb: &mut Ambient<'a>) -> Result<Option<&'t MappingTarget>, RuntimeError> {
+    for target in targets {
+        if !target.applies_to(window) { continue; }
+        if let Some(guard) = &target.guard {
+            match eval_expr(guard, &target.var_map, amb).await? {
+                ValueType::Bool(true) => {}
+                ValueType::Bool(false) => continue,
+                value => return Err(RuntimeError::type_mismatch(format!("mapping guards need to evaluate to a boolean, got {}", value.type_name()))),
+            }
+        }
+        return Ok(Some(target));
+    }
+    Ok(None)
 }
 
 fn arc_mutexes_are_equal<T>(first: &Arc<Mutex<T>>, second: &Arc<Mutex<T>>) -> bool
     where T: PartialEq { Arc::ptr_eq(first, second) || *first.lock().unwrap() == *second.lock().unwrap() }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct Block {
     pub(crate) statements: Vec<Stmt>,
+    /// the location of each statement, only set for blocks that were parsed from a script
+    pub(crate) spans: Vec<Span>,
 }
 
 impl Block {
     pub(crate) fn new() -> Self {
-        Block { statements: vec![] }
+        Block { statements: vec![], spans: vec![] }
     }
+
+    pub(crate) fn span(&self, idx: usize) -> Span {
+        self.spans.get(idx).cloned().unwrap_or_default()
+    }
+}
+
+// locations only matter for error messages
+impl PartialEq for Block {
+    fn eq(&self, other: &Self) -> bool { self.statements == other.statements }
 }
 
 
@@ -398,22 +651,36 @@ pub enum Expr {
     Neq(Box<Expr>, Box<Expr>),
     LT(Box<Expr>, Box<Expr>),
     GT(Box<Expr>, Box<Expr>),
+    LE(Box<Expr>, Box<Expr>),
+    GE(Box<Expr>, Box<Expr>),
     // Inc(Expr),
     // Dec(Expr),
     Add(Box<Expr>, Box<Expr>),
     Sub(Box<Expr>, Box<Expr>),
     Div(Box<Expr>, Box<Expr>),
     Mul(Box<Expr>, Box<Expr>),
+    Mod(Box<Expr>, Box<Expr>),
     Neg(Box<Expr>),
+    Minus(Box<Expr>),
     And(Box<Expr>, Box<Expr>),
     Or(Box<Expr>, Box<Expr>),
     Init(String, Box<Expr>),
     Assign(String, Box<Expr>),
     KeyMapping(Vec<KeyMapping>),
+    GuardedKeyMapping(Vec<KeyMapping>, Box<Expr>),
+    /// A click mapping, optionally guarded, that is repeated in software while its key is held.
+    RepeatKeyMapping(Box<Expr>, SoftwareRepeat),
+    TapHoldMapping(Key, TapHold),
+    ChordMapping(Vec<Key>, Block, Block),
+    SequenceMapping(Vec<SequenceStep>, Block),
+    RelMapping(RelTrigger, Option<Box<Expr>>, Block),
 
     Name(String),
     Value(ValueType),
     Lambda(Vec<String>, Block),
+    List(Vec<Expr>),
+    Map(Vec<(String, Expr)>),
+    Index(Box<Expr>, Box<Expr>),
 
     FunctionCall(String, Vec<Expr>),
 
@@ -432,7 +699,16 @@ pub(crate) enum Stmt {
     Block(Block),
     If(Vec<(Expr, Block)>, Option<Block>),
     For(Expr, Expr, Expr, Block),
-    // While
+    ForIn(String, Expr, Block),
+    While(Expr, Block),
+    WhenWindow(WindowCondition, Block),
+    /// An import as written in the script, replaced by the imported statements before evaluation.
+    Import(String, Option<String>),
+    /// A named function, defined before the other statements of its block run.
+    Function(String, Vec<String>, Block),
+    /// The statements of a file imported under a namespace.
+    Namespace(String, Block),
     Return(Expr),
     Continue,
+    Break,
 }
\ No newline at end of file
@@ -1,3 +1,7 @@
 pub mod evaluation;
+pub mod error;
+pub mod environment;
+pub mod concurrency;
 mod builtin_functions;
 
+pub(crate) use builtin_functions::builtin_arity;
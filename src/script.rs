@@ -1,9 +1,11 @@
 use crate::*;
 use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
 use crate::messaging::ExecutionMessage;
 
 
-pub fn parse_script(script_file: &mut fs::File) -> Block {
+/// Parses the script along with all the files that it imports.
+pub fn parse_script(script_file: &mut fs::File, script_name: &str) -> Result<Block> {
     let script_file_length = script_file.seek(SeekFrom::End(0))
         .map_err(|err| anyhow!("failed seek operation on script file: {}", err))
         .unwrap();
@@ -18,9 +20,9 @@ pub fn parse_script(script_file: &mut fs::File) -> Block {
         .map_err(|err| anyhow!("failed to read script file: {}", err))
         .unwrap();
 
-    let global = parsing::parser::parse_script(&*raw).unwrap();
+    let global = parsing::parser::parse_script(&*raw, script_name)?;
 
-    global
+    import::resolve_imports(global, Path::new(script_name))
 }
 
 
@@ -35,7 +37,12 @@ pub async fn evaluate_script(
         window_cycle_token,
         message_tx: Some(&mut execution_message_tx),
         modifier_state: &KeyModifierState::new(),
+        layer: None,
+        window: None,
+        rel_frame: false,
     };
 
-    eval_block(&script_ast, &mut GuardedVarMap::new(Mutex::new(VarMap::new(None))), &mut amb).await;
+    if let Err(err) = eval_block(&script_ast, &Environment::new(), &mut amb).await {
+        let _ = execution_message_tx.send(ExecutionMessage::RuntimeError(err)).await;
+    }
 }
@@ -0,0 +1,373 @@
+use crate::*;
+
+pub const DEFAULT_SEQUENCE_TIMEOUT: time::Duration = time::Duration::from_millis(1000);
+
+/// A single step in a sequence trigger such as `<leader> g s`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SequenceStep {
+    Leader,
+    Key(KeyClickActionWithMods),
+}
+
+#[derive(Debug, Clone)]
+pub struct SequenceMapping {
+    pub id: usize,
+    pub steps: Vec<SequenceStep>,
+    pub block: Block,
+    pub var_map: Environment,
+}
+
+#[derive(Default)]
+struct SequenceNode {
+    children: HashMap<SequenceStep, SequenceNode>,
+    mapping: Option<(Block, Environment)>,
+    // the id of the mapping, only meaningful if a mapping is set
+    id: usize,
+}
+
+impl SequenceNode {
+    /// Removes the mapping along with the nodes that lead to nothing else, returns whether it was found.
+    fn remove_id(&mut self, id: usize) -> bool {
+        let mut removed = false;
+        if self.mapping.is_some() && self.id == id {
+            self.mapping = None;
+            removed = true;
+        }
+        for child in self.children.values_mut() {
+            removed |= child.remove_id(id);
+        }
+        self.children.retain(|_, child| child.mapping.is_some() || !child.children.is_empty());
+        removed
+    }
+}
+
+/// The result of feeding an event to the sequence matcher.
+pub struct SequenceOutcome {
+    /// events that should continue being processed, in their original order
+    pub replay: Vec<InputEvent>,
+    /// a block that should be run because a sequence was completed
+    pub fire: Option<(Block, Environment)>,
+    /// a timer that should fire `SequenceTimeout` with the given id after the duration
+    pub timer: Option<(usize, time::Duration)>,
+}
+
+impl SequenceOutcome {
+    fn replay(replay: Vec<InputEvent>) -> Self { SequenceOutcome { replay, fire: None, timer: None } }
+    fn consumed() -> Self { SequenceOutcome { replay: vec![], fire: None, timer: None } }
+}
+
+pub struct SequenceTriggerState {
+    root: SequenceNode,
+    pub leader: KeyClickActionWithMods,
+    pub timeout: time::Duration,
+    progress: Vec<SequenceStep>,
+    swallowed: Vec<InputEvent>,
+    // keys of the current sequence that are still held down
+    held: Vec<Key>,
+    // keys of a completed sequence, their remaining events are dropped
+    swallow_until_up: Vec<Key>,
+    timer_id: usize,
+}
+
+impl SequenceTriggerState {
+    pub fn new() -> Self {
+        SequenceTriggerState {
+            root: Default::default(),
+            leader: KeyClickActionWithMods::new(*KEY_BACKSLASH),
+            timeout: DEFAULT_SEQUENCE_TIMEOUT,
+            progress: vec![],
+            swallowed: vec![],
+            held: vec![],
+            swallow_until_up: vec![],
+            timer_id: 0,
+        }
+    }
+
+    /// Adds a sequence mapping, the leader key is only resolved once the step is matched.
+    pub fn add_mapping(&mut self, mapping: SequenceMapping) {
+        let mut node = &mut self.root;
+        for step in mapping.steps {
+            node = node.children.entry(step).or_default();
+        }
+        node.mapping = Some((mapping.block, mapping.var_map));
+        node.id = mapping.id;
+    }
+
+    /// Removes the sequence defined by the mapping, returns the events of a sequence in progress
+    /// which need to be replayed.
+    pub fn remove_id(&mut self, id: usize) -> Vec<InputEvent> {
+        if !self.root.remove_id(id) { return vec![]; }
+        // the sequence in progress might have been removed
+        self.abort()
+    }
+
+    /// Removes all sequences, returns the events of a sequence in progress which need to be replayed.
+    pub fn clear(&mut self) -> Vec<InputEvent> {
+        self.root = Default::default();
+        self.abort()
+    }
+
+    fn current_node(&self) -> &SequenceNode {
+        let mut node = &self.root;
+        for step in &self.progress {
+            node = node.children.get(step).unwrap();
+        }
+        node
+    }
+
+    fn next_timer(&mut self) -> Option<(usize, time::Duration)> {
+        self.timer_id += 1;
+        Some((self.timer_id, self.timeout))
+    }
+
+    fn complete(&mut self, mapping: (Block, Environment)) -> SequenceOutcome {
+        self.progress.clear();
+        self.swallow_until_up.append(&mut self.held);
+        // modifiers typed during the sequence still need to reach the output
+        let replay = std::mem::take(&mut self.swallowed).into_iter()
+            .filter(|ev| Key { event_code: ev.event_code }.is_modifier())
+            .collect();
+        SequenceOutcome { replay, fire: Some(mapping), timer: None }
+    }
+
+    fn abort(&mut self) -> Vec<InputEvent> {
+        self.progress.clear();
+        self.held.clear();
+        std::mem::take(&mut self.swallowed)
+    }
+
+    /// Finds the step of the current node that a key press matches, side-specific steps take
+    /// precedence.
+    fn matching_step(&self, key: Key, modifiers: &KeyModifierState) -> Option<SequenceStep> {
+        let node = self.current_node();
+        modifiers.matching_flags().into_iter()
+            .find_map(|flags| {
+                let step = SequenceStep::Key(KeyClickActionWithMods::new_with_mods(key, flags));
+                if node.children.contains_key(&step) { return Some(step); }
+
+                let is_leader = self.leader == KeyClickActionWithMods::new_with_mods(key, flags);
+                if is_leader && node.children.contains_key(&SequenceStep::Leader) { return Some(SequenceStep::Leader); }
+                None
+            })
+    }
+
+    /// Applies the modifier events swallowed by the sequence in progress to the given state.
+    fn step_modifiers(&self, modifiers: &KeyModifierState) -> KeyModifierState {
+        let mut modifiers = *modifiers;
+        for ev in &self.swallowed {
+            let key = Key { event_code: ev.event_code };
+            let side = if key == *KEY_LEFT_CTRL { &mut modifiers.left_ctrl }
+            else if key == *KEY_RIGHT_CTRL { &mut modifiers.right_ctrl }
+            else if key == *KEY_LEFT_SHIFT { &mut modifiers.left_shift }
+            else if key == *KEY_RIGHT_SHIFT { &mut modifiers.right_shift }
+            else if key == *KEY_LEFT_ALT { &mut modifiers.left_alt }
+            else if key == *KEY_RIGHT_ALT { &mut modifiers.right_alt }
+            else if key == *KEY_LEFT_META { &mut modifiers.left_meta }
+            else if key == *KEY_RIGHT_META { &mut modifiers.right_meta }
+            else { continue; };
+            if ev.value == TYPE_DOWN { *side = true; } else if ev.value == TYPE_UP { *side = false; }
+        }
+        modifiers
+    }
+
+    pub fn handle_input_ev(&mut self, ev: InputEvent, modifiers: &KeyModifierState) -> SequenceOutcome {
+        let key = Key { event_code: ev.event_code };
+
+        // modifiers are needed to type the steps, so they never affect the sequence, but they
+        // are held back during a sequence to be replayed in their original order
+        if key.is_modifier() {
+            if self.progress.is_empty() { return SequenceOutcome::replay(vec![ev]); }
+            self.swallowed.push(ev);
+            return SequenceOutcome::consumed();
+        }
+        let modifiers = &self.step_modifiers(modifiers);
+
+        if ev.value != TYPE_DOWN {
+            if let Some(idx) = self.swallow_until_up.iter().position(|v| *v == key) {
+                if ev.value == TYPE_UP { self.swallow_until_up.remove(idx); }
+                return SequenceOutcome::consumed();
+            }
+
+            if let Some(idx) = self.held.iter().position(|v| *v == key) {
+                if ev.value == TYPE_UP {
+                    self.held.remove(idx);
+                    self.swallowed.push(ev);
+                }
+                return SequenceOutcome::consumed();
+            }
+
+            return SequenceOutcome::replay(vec![ev]);
+        }
+
+        let next = self.matching_step(key, modifiers)
+            .map(|step| (step, &self.current_node().children[&step]))
+            .map(|(step, node)| (step, node.children.is_empty(), node.mapping.clone()));
+
+        match next {
+            Some((step, is_leaf, mapping)) => {
+                self.progress.push(step);
+                self.swallowed.push(ev);
+                self.held.push(key);
+
+                if is_leaf {
+                    if let Some(mapping) = mapping {
+                        return self.complete(mapping);
+                    }
+                }
+
+                let timer = self.next_timer();
+                SequenceOutcome { replay: vec![], fire: None, timer }
+            }
+            None if self.progress.is_empty() => SequenceOutcome::replay(vec![ev]),
+            None => {
+                // the sequence was broken, the current key might still start a new one
+                let mut replay = self.abort();
+                let mut outcome = self.handle_input_ev(ev, modifiers);
+                replay.append(&mut outcome.replay);
+                outcome.replay = replay;
+                outcome
+            }
+        }
+    }
+
+    /// Called once the sequence timeout expires, either completes an ambiguous sequence or
+    /// returns the swallowed events.
+    pub fn handle_timeout(&mut self, id: usize) -> SequenceOutcome {
+        if id != self.timer_id || self.progress.is_empty() { return SequenceOutcome::consumed(); }
+
+        match self.current_node().mapping.clone() {
+            Some(mapping) => self.complete(mapping),
+            None => SequenceOutcome::replay(self.abort()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> SequenceTriggerState {
+        let mut state = SequenceTriggerState::new();
+        state.add_mapping(SequenceMapping {
+            id: 0,
+            steps: vec![SequenceStep::Leader, SequenceStep::Key(KeyClickActionWithMods::new(*KEY_G)), SequenceStep::Key(KeyClickActionWithMods::new(*KEY_S))],
+            block: Block::new(),
+            var_map: Environment::new(),
+        });
+        state
+    }
+
+    fn ev(key: Key, value: i32) -> InputEvent { KeyAction::new(key, value).to_input_ev() }
+
+    #[test]
+    fn test_sequence() {
+        let mut state = state();
+        let leader = state.leader.key;
+        let mods = KeyModifierState::new();
+
+        let res = state.handle_input_ev(ev(leader, TYPE_DOWN), &mods);
+        assert_eq!(res.replay, vec![]);
+        assert!(res.timer.is_some());
+        assert_eq!(state.handle_input_ev(ev(leader, TYPE_UP), &mods).replay, vec![]);
+        assert_eq!(state.handle_input_ev(ev(*KEY_G, TYPE_DOWN), &mods).replay, vec![]);
+        assert_eq!(state.handle_input_ev(ev(*KEY_G, TYPE_UP), &mods).replay, vec![]);
+
+        let res = state.handle_input_ev(ev(*KEY_S, TYPE_DOWN), &mods);
+        assert!(res.fire.is_some());
+
+        let res = state.handle_input_ev(ev(*KEY_S, TYPE_UP), &mods);
+        assert_eq!(res.replay, vec![]);
+        assert!(res.fire.is_none());
+    }
+
+    #[test]
+    fn test_broken_sequence() {
+        let mut state = state();
+        let leader = state.leader.key;
+        let mods = KeyModifierState::new();
+
+        state.handle_input_ev(ev(leader, TYPE_DOWN), &mods);
+        state.handle_input_ev(ev(leader, TYPE_UP), &mods);
+        state.handle_input_ev(ev(*KEY_G, TYPE_DOWN), &mods);
+
+        let res = state.handle_input_ev(ev(*KEY_A, TYPE_DOWN), &mods);
+        assert_eq!(res.replay, vec![ev(leader, TYPE_DOWN), ev(leader, TYPE_UP), ev(*KEY_G, TYPE_DOWN), ev(*KEY_A, TYPE_DOWN)]);
+
+        // the key was replayed, so its release is no longer swallowed
+        assert_eq!(state.handle_input_ev(ev(*KEY_G, TYPE_UP), &mods).replay, vec![ev(*KEY_G, TYPE_UP)]);
+    }
+
+    #[test]
+    fn test_modifier_during_sequence() {
+        let mut state = state();
+        let leader = state.leader.key;
+        let mods = KeyModifierState::new();
+
+        state.handle_input_ev(ev(leader, TYPE_DOWN), &mods);
+        state.handle_input_ev(ev(leader, TYPE_UP), &mods);
+        assert_eq!(state.handle_input_ev(ev(*KEY_LEFT_SHIFT, TYPE_DOWN), &mods).replay, vec![]);
+
+        // the swallowed shift applies to the lookup, so 'G' doesn't match 'g'
+        let res = state.handle_input_ev(ev(*KEY_G, TYPE_DOWN), &mods);
+        assert_eq!(res.replay, vec![ev(leader, TYPE_DOWN), ev(leader, TYPE_UP), ev(*KEY_LEFT_SHIFT, TYPE_DOWN), ev(*KEY_G, TYPE_DOWN)]);
+    }
+
+    #[test]
+    fn test_sequence_timeout() {
+        let mut state = state();
+        let leader = state.leader.key;
+        let mods = KeyModifierState::new();
+
+        let (id, _) = state.handle_input_ev(ev(leader, TYPE_DOWN), &mods).timer.unwrap();
+        assert_eq!(state.handle_timeout(id).replay, vec![ev(leader, TYPE_DOWN)]);
+        assert_eq!(state.handle_input_ev(ev(leader, TYPE_UP), &mods).replay, vec![ev(leader, TYPE_UP)]);
+    }
+
+    #[test]
+    fn test_side_specific_step() {
+        let mut state = SequenceTriggerState::new();
+        let mut flags = KeyModifierFlags::new();
+        flags.set(Modifier::Ctrl, ModifierSide::Left);
+        state.add_mapping(SequenceMapping {
+            id: 0,
+            steps: vec![SequenceStep::Key(KeyClickActionWithMods::new(*KEY_G)), SequenceStep::Key(KeyClickActionWithMods::new_with_mods(*KEY_T, flags))],
+            block: Block::new(),
+            var_map: Environment::new(),
+        });
+
+        let mut mods = KeyModifierState::new();
+        state.handle_input_ev(ev(*KEY_G, TYPE_DOWN), &mods);
+        state.handle_input_ev(ev(*KEY_G, TYPE_UP), &mods);
+        mods.left_ctrl = true;
+        assert!(state.handle_input_ev(ev(*KEY_T, TYPE_DOWN), &mods).fire.is_some());
+    }
+
+    #[test]
+    fn test_leader_change() {
+        let mut state = state();
+        state.leader = KeyClickActionWithMods::new(*KEY_SPACE);
+        let mods = KeyModifierState::new();
+
+        assert_eq!(state.handle_input_ev(ev(*KEY_BACKSLASH, TYPE_DOWN), &mods).replay, vec![ev(*KEY_BACKSLASH, TYPE_DOWN)]);
+        state.handle_input_ev(ev(*KEY_BACKSLASH, TYPE_UP), &mods);
+        state.handle_input_ev(ev(*KEY_SPACE, TYPE_DOWN), &mods);
+        state.handle_input_ev(ev(*KEY_G, TYPE_DOWN), &mods);
+        assert!(state.handle_input_ev(ev(*KEY_S, TYPE_DOWN), &mods).fire.is_some());
+    }
+
+    #[test]
+    fn test_remove_sequence() {
+        let mut state = state();
+        let leader = state.leader.key;
+        let mods = KeyModifierState::new();
+
+        state.handle_input_ev(ev(leader, TYPE_DOWN), &mods);
+        assert_eq!(state.remove_id(1), vec![]);
+        assert_eq!(state.remove_id(0), vec![ev(leader, TYPE_DOWN)]);
+
+        // the leader no longer starts a sequence
+        assert_eq!(state.handle_input_ev(ev(leader, TYPE_UP), &mods).replay, vec![ev(leader, TYPE_UP)]);
+        assert_eq!(state.handle_input_ev(ev(leader, TYPE_DOWN), &mods).replay, vec![ev(leader, TYPE_DOWN)]);
+    }
+}
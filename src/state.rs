@@ -1,17 +1,191 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use ignore_list::*;
 
 use crate::*;
 
+static NEXT_MAPPING_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a new id that identifies all the targets added by a single mapping definition.
+pub fn next_mapping_id() -> usize { NEXT_MAPPING_ID.fetch_add(1, Ordering::Relaxed) }
+
+/// The block a trigger is mapped to, guarded mappings only run if the guard evaluates to true and
+/// window-scoped mappings only while a matching window is focused.
+#[derive(Clone, Debug)]
+pub struct MappingTarget {
+    pub id: usize,
+    pub guard: Option<Expr>,
+    pub window: Option<WindowCondition>,
+    /// how the key is repeated in software while it's held, only set for key presses
+    pub repeat: Option<SoftwareRepeat>,
+    pub block: Block,
+    pub var_map: Environment,
+}
+
+impl MappingTarget {
+    fn is_conditional(&self) -> bool { self.guard.is_some() || self.window.is_some() }
+
+    pub fn applies_to(&self, window: Option<&ActiveWindowInfo>) -> bool {
+        self.window.as_ref().map_or(true, |v| v.matches(window))
+    }
+}
+
+/// Adds a target to the targets of a trigger, conditional targets come before the unconditional one.
+pub(crate) fn with_target(targets: &[MappingTarget], target: MappingTarget) -> Vec<MappingTarget> {
+    let mut targets = targets.to_vec();
+    targets.retain(|v| v.guard != target.guard || v.window != target.window);
+
+    let idx = if target.is_conditional() { targets.iter().position(|v| !v.is_conditional()) } else { None };
+    match idx {
+        Some(idx) => targets.insert(idx, target),
+        None => targets.push(target),
+    }
+    targets
+}
+
+/// How the press of a key was handled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PressedTarget {
+    /// the id of the target that handled the press, `None` if the press was forwarded
+    pub handled_by: Option<usize>,
+    /// the ids of all targets of the press, the conditions of these mappings aren't checked again
+    /// for the repeat and release events
+    pub considered: Vec<usize>,
+}
+
+/// The targets that handled the presses of keys that are still held down. Repeat and release events
+/// go to the same target, even if its guard or window no longer holds. Shared with the mapping
+/// handlers, which run in event order for each key.
+#[derive(Clone, Default)]
+pub struct PressedTargets(Arc<Mutex<HashMap<Key, PressedTarget>>>);
+
+impl PressedTargets {
+    pub fn new() -> Self { Default::default() }
+
+    pub fn press(&self, key: Key, target: PressedTarget) {
+        self.0.lock().unwrap().insert(key, target);
+    }
+
+    /// Returns how the press of the key was handled, the entry is removed once the key is released.
+    pub fn pinned(&self, key: Key, value: i32) -> Option<PressedTarget> {
+        let mut pressed = self.0.lock().unwrap();
+        if value == TYPE_UP { pressed.remove(&key) } else { pressed.get(&key).cloned() }
+    }
+
+    /// Returns the id of the target that handled the press of a key that is still held down.
+    pub fn handled_by(&self, key: Key) -> Option<usize> {
+        self.0.lock().unwrap().get(&key).and_then(|v| v.handled_by)
+    }
+}
+
+/// Mapping targets that were removed while their key was held down, the repeat and release events
+/// of the key still go to the target that handled its press.
+#[derive(Default)]
+pub struct RemovedTargets(HashMap<Key, CompiledKeyMappings>);
+
+impl RemovedTargets {
+    pub fn new() -> Self { Default::default() }
+
+    /// Keeps the removed targets of the keys whose press they handled.
+    pub fn keep(&mut self, removed: CompiledKeyMappings, pressed: &PressedTargets) {
+        for (from, targets) in removed.0 {
+            if from.value == TYPE_DOWN { continue; }
+            let id = match pressed.handled_by(from.key) {
+                Some(id) => id,
+                None => continue,
+            };
+            if let Some(target) = targets.iter().find(|v| v.id == id) {
+                self.0.entry(from.key).or_insert_with(CompiledKeyMappings::new)
+                    .0.insert(from, Arc::new(vec![target.clone()]));
+            }
+        }
+    }
+
+    /// Looks up a kept target for a repeat or release event, the targets of a key are dropped once
+    /// it is released or pressed again.
+    pub fn lookup(&mut self, candidates: &[KeyActionWithMods]) -> Option<Arc<Vec<MappingTarget>>> {
+        let action = candidates.first()?;
+        if action.value == TYPE_DOWN {
+            self.0.remove(&action.key);
+            return None;
+        }
+
+        let targets = candidates.iter().find_map(|v| self.0.get(&action.key)?.0.get(v)).cloned();
+        if action.value == TYPE_UP { self.0.remove(&action.key); }
+        targets
+    }
+}
+
 #[derive(Clone, Debug)]
-pub struct CompiledKeyMappings(pub HashMap<KeyActionWithMods, Arc<(Block, GuardedVarMap)>>);
+pub struct CompiledKeyMappings(pub HashMap<KeyActionWithMods, Arc<Vec<MappingTarget>>>);
+
+impl CompiledKeyMappings {
+    pub fn new() -> Self { CompiledKeyMappings(Default::default()) }
+
+    /// Adds a mapping target, conditional targets are tried in the order they were added and the
+    /// unconditional target always comes last. Re-mapping a trigger with the same conditions replaces it.
+    pub fn insert(&mut self, from: KeyActionWithMods, target: MappingTarget) {
+        let targets = with_target(self.0.get(&from).map(|v| v.as_slice()).unwrap_or_default(), target);
+        self.0.insert(from, Arc::new(targets));
+    }
+
+    /// Removes the mapping targets that were added by a single mapping definition, returns true if
+    /// there were any.
+    pub fn remove_id(&mut self, id: usize) -> bool {
+        !self.take_id(id).0.is_empty()
+    }
+
+    /// Removes the mapping targets that were added by a single mapping definition and returns them.
+    pub fn take_id(&mut self, id: usize) -> CompiledKeyMappings {
+        let mut removed = CompiledKeyMappings::new();
+        self.0.retain(|from, targets| {
+            if !targets.iter().any(|v| v.id == id) { return true; }
+            removed.0.insert(*from, Arc::new(targets.iter().filter(|v| v.id == id).cloned().collect()));
+            *targets = Arc::new(targets.iter().filter(|v| v.id != id).cloned().collect());
+            !targets.is_empty()
+        });
+        removed
+    }
+
+    /// Describes the mapped triggers in script syntax, triggers that are mapped on press and release
+    /// are listed as a single key click.
+    pub fn triggers(&self) -> Vec<String> {
+        let is_click = |from: &KeyActionWithMods| {
+            self.0.contains_key(&KeyActionWithMods::new(from.key, TYPE_DOWN, from.modifiers)) &&
+                self.0.contains_key(&KeyActionWithMods::new(from.key, TYPE_UP, from.modifiers))
+        };
 
-impl CompiledKeyMappings { pub fn new() -> Self { CompiledKeyMappings(Default::default()) } }
+        let mut triggers: Vec<String> = self.0.keys().filter_map(|from| {
+            if is_click(from) {
+                if from.value != TYPE_DOWN { return None; }
+                return Some(format!("{}{}", from.modifiers, from.key));
+            }
+
+            let value = if from.value == TYPE_DOWN { "down" } else if from.value == TYPE_UP { "up" } else { "repeat" };
+            Some(format!("{}{{{} {}}}", from.modifiers, from.key, value))
+        }).collect();
+
+        triggers.sort();
+        triggers
+    }
+}
 
 pub struct State {
     pub modifiers: Arc<KeyModifierState>,
 
     pub ignore_list: IgnoreList,
     pub active_window: Option<ActiveWindowInfo>,
+    pub tap_hold: TapHoldState,
+    pub chords: ChordState,
+    pub sequences: SequenceTriggerState,
+    pub layers: LayerState,
+    pub sticky_modifiers: StickyModifierState,
+    pub timers: TimerState,
+    pub handler_queues: HandlerQueues,
+    pub key_repeat: KeyRepeatState,
+    pub rel_mappings: RelMappings,
+    pub pressed_targets: PressedTargets,
+    pub removed_targets: RemovedTargets,
 }
 
 
@@ -21,6 +195,88 @@ impl State {
             modifiers: Arc::new(KeyModifierState::new()),
             ignore_list: IgnoreList::new(),
             active_window: None,
+            tap_hold: TapHoldState::new(),
+            chords: ChordState::new(),
+            sequences: SequenceTriggerState::new(),
+            layers: LayerState::new(),
+            sticky_modifiers: StickyModifierState::new(),
+            timers: TimerState::new(),
+            handler_queues: HandlerQueues::new(),
+            key_repeat: KeyRepeatState::new(),
+            rel_mappings: RelMappings::new(),
+            pressed_targets: PressedTargets::new(),
+            removed_targets: RemovedTargets::new(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(id: usize, guard: Option<Expr>) -> MappingTarget {
+        MappingTarget { id, guard, window: None, repeat: None, block: Block::new(), var_map: Environment::new() }
+    }
+
+    fn action(key: Key, value: i32) -> KeyActionWithMods { KeyActionWithMods::new(key, value, KeyModifierFlags::new()) }
+
+    #[test]
+    fn test_insert_order() {
+        let mut mappings = CompiledKeyMappings::new();
+        let guard = |v| Some(Expr::Value(ValueType::Bool(v)));
+
+        mappings.insert(action(*KEY_A, TYPE_DOWN), target(0, None));
+        mappings.insert(action(*KEY_A, TYPE_DOWN), target(1, guard(true)));
+        mappings.insert(action(*KEY_A, TYPE_DOWN), target(2, guard(false)));
+        // re-mapping with the same guard replaces the existing target
+        mappings.insert(action(*KEY_A, TYPE_DOWN), target(3, guard(true)));
+
+        let ids: Vec<usize> = mappings.0.get(&action(*KEY_A, TYPE_DOWN)).unwrap().iter().map(|v| v.id).collect();
+        assert_eq!(ids, vec![2, 3, 0]);
+    }
+
+    #[test]
+    fn test_remove_id() {
+        let mut mappings = CompiledKeyMappings::new();
+        mappings.insert(action(*KEY_A, TYPE_DOWN), target(0, None));
+        mappings.insert(action(*KEY_A, TYPE_UP), target(0, None));
+        mappings.insert(action(*KEY_B, TYPE_DOWN), target(1, None));
+
+        assert_eq!(mappings.triggers(), vec!["a".to_string(), "{b down}".to_string()]);
+
+        assert!(mappings.remove_id(0));
+        assert!(!mappings.remove_id(0));
+        assert_eq!(mappings.triggers(), vec!["{b down}".to_string()]);
+    }
+
+    #[test]
+    fn test_pressed_targets() {
+        let pressed = PressedTargets::new();
+        assert_eq!(pressed.pinned(*KEY_A, TYPE_UP), None);
+
+        let target = PressedTarget { handled_by: Some(3), considered: vec![3, 4] };
+        pressed.press(*KEY_A, target.clone());
+        assert_eq!(pressed.pinned(*KEY_A, TYPE_REPEAT), Some(target.clone()));
+        assert_eq!(pressed.pinned(*KEY_A, TYPE_UP), Some(target));
+        assert_eq!(pressed.pinned(*KEY_A, TYPE_UP), None);
+    }
+
+    #[test]
+    fn test_removed_targets() {
+        let mut mappings = CompiledKeyMappings::new();
+        mappings.insert(action(*KEY_A, TYPE_DOWN), target(0, None));
+        mappings.insert(action(*KEY_A, TYPE_UP), target(0, None));
+        mappings.insert(action(*KEY_B, TYPE_UP), target(0, None));
+
+        let pressed = PressedTargets::new();
+        pressed.press(*KEY_A, PressedTarget { handled_by: Some(0), considered: vec![0] });
+
+        // only the held key keeps its release
+        let mut removed = RemovedTargets::new();
+        removed.keep(mappings.take_id(0), &pressed);
+        assert!(mappings.0.is_empty());
+        assert!(removed.lookup(&[action(*KEY_B, TYPE_UP)]).is_none());
+        assert_eq!(removed.lookup(&[action(*KEY_A, TYPE_UP)]).unwrap()[0].id, 0);
+        assert!(removed.lookup(&[action(*KEY_A, TYPE_UP)]).is_none());
+    }
+}
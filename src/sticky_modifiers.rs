@@ -0,0 +1,182 @@
+use crate::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Sticky {
+    /// applies to the next key press, then releases
+    OneShot,
+    /// stays down until the modifier is tapped again
+    Locked,
+}
+
+/// The result of feeding an event to the sticky modifier tracker.
+pub struct StickyModifierOutcome {
+    /// whether the original event was consumed
+    pub consumed: bool,
+    /// actions that need to be emitted after the original event was processed
+    pub after: Vec<KeyAction>,
+}
+
+impl StickyModifierOutcome {
+    fn passthrough() -> Self { StickyModifierOutcome { consumed: false, after: vec![] } }
+    fn consumed() -> Self { StickyModifierOutcome { consumed: true, after: vec![] } }
+}
+
+/// Turns tapped modifiers into one-shot modifiers, their release is held back until the next key
+/// press was released.
+pub struct StickyModifierState {
+    pub enabled: bool,
+    pub lock_on_double_tap: bool,
+    sticky: HashMap<Key, Sticky>,
+    // modifiers that are physically held down
+    held: Vec<Key>,
+    // modifiers that are held down while no other key was pressed
+    tapping: Vec<Key>,
+    // the key press that the one-shot modifiers apply to
+    applied_to: Option<Key>,
+}
+
+impl StickyModifierState {
+    pub fn new() -> Self {
+        StickyModifierState { enabled: false, lock_on_double_tap: false, sticky: Default::default(), held: vec![], tapping: vec![], applied_to: None }
+    }
+
+    /// Releases all sticky modifiers, returns the actions that need to be emitted.
+    pub fn release_all(&mut self) -> Vec<KeyAction> {
+        self.tapping.clear();
+        self.applied_to = None;
+        let held = &self.held;
+        self.sticky.drain()
+            .filter(|(key, _)| !held.contains(key))
+            .map(|(key, _)| KeyAction::new(key, TYPE_UP))
+            .collect()
+    }
+
+    fn release_one_shot(&mut self) -> Vec<KeyAction> {
+        let keys: Vec<Key> = self.sticky.iter()
+            .filter(|(_, sticky)| **sticky == Sticky::OneShot)
+            .map(|(key, _)| *key)
+            .collect();
+
+        keys.into_iter().map(|key| {
+            self.sticky.remove(&key);
+            KeyAction::new(key, TYPE_UP)
+        }).collect()
+    }
+
+    pub fn handle_key_action(&mut self, action: &KeyAction) -> StickyModifierOutcome {
+        if !self.enabled { return StickyModifierOutcome::passthrough(); }
+
+        if !action.key.is_modifier() {
+            if action.value == TYPE_DOWN {
+                self.tapping.clear();
+                // sticky modifiers that were pressed again are used as regular modifiers
+                let held = &self.held;
+                self.sticky.retain(|key, _| !held.contains(key));
+                if self.applied_to.is_none() && self.sticky.values().any(|v| *v == Sticky::OneShot) {
+                    self.applied_to = Some(action.key);
+                }
+            } else if action.value == TYPE_UP && self.applied_to == Some(action.key) {
+                self.applied_to = None;
+                return StickyModifierOutcome { consumed: false, after: self.release_one_shot() };
+            }
+            return StickyModifierOutcome::passthrough();
+        }
+
+        // sticky modifiers are still down, there is no need to forward them again
+        if action.value == TYPE_DOWN {
+            self.held.push(action.key);
+            self.tapping.push(action.key);
+            return if self.sticky.contains_key(&action.key) { StickyModifierOutcome::consumed() } else { StickyModifierOutcome::passthrough() };
+        }
+        if action.value != TYPE_UP {
+            return if self.sticky.contains_key(&action.key) { StickyModifierOutcome::consumed() } else { StickyModifierOutcome::passthrough() };
+        }
+
+        self.held.retain(|v| *v != action.key);
+
+        let was_tapped = match self.tapping.iter().position(|v| *v == action.key) {
+            Some(idx) => {
+                self.tapping.remove(idx);
+                true
+            }
+            None => false,
+        };
+
+        // the modifier was used as a regular modifier
+        if !was_tapped {
+            self.sticky.remove(&action.key);
+            return StickyModifierOutcome::passthrough();
+        }
+
+        match self.sticky.get(&action.key) {
+            None => {
+                self.sticky.insert(action.key, Sticky::OneShot);
+                StickyModifierOutcome::consumed()
+            }
+            Some(Sticky::OneShot) if self.lock_on_double_tap => {
+                self.sticky.insert(action.key, Sticky::Locked);
+                StickyModifierOutcome::consumed()
+            }
+            Some(_) => {
+                self.sticky.remove(&action.key);
+                StickyModifierOutcome::passthrough()
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> StickyModifierState {
+        let mut state = StickyModifierState::new();
+        state.enabled = true;
+        state
+    }
+
+    #[test]
+    fn test_one_shot() {
+        let mut state = state();
+
+        assert!(!state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_DOWN)).consumed);
+        assert!(state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_UP)).consumed);
+
+        assert!(!state.handle_key_action(&KeyAction::new(*KEY_A, TYPE_DOWN)).consumed);
+        let res = state.handle_key_action(&KeyAction::new(*KEY_A, TYPE_UP));
+        assert!(!res.consumed);
+        assert_eq!(res.after, vec![KeyAction::new(*KEY_LEFT_SHIFT, TYPE_UP)]);
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_B, TYPE_UP));
+        assert_eq!(res.after, vec![]);
+    }
+
+    #[test]
+    fn test_regular_modifier() {
+        let mut state = state();
+
+        state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_DOWN));
+        state.handle_key_action(&KeyAction::new(*KEY_A, TYPE_DOWN));
+        state.handle_key_action(&KeyAction::new(*KEY_A, TYPE_UP));
+        assert!(!state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_UP)).consumed);
+        assert_eq!(state.release_all(), vec![]);
+    }
+
+    #[test]
+    fn test_lock_on_double_tap() {
+        let mut state = state();
+        state.lock_on_double_tap = true;
+
+        for _ in 0..2 {
+            state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_DOWN));
+            assert!(state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_UP)).consumed);
+        }
+
+        state.handle_key_action(&KeyAction::new(*KEY_A, TYPE_DOWN));
+        assert_eq!(state.handle_key_action(&KeyAction::new(*KEY_A, TYPE_UP)).after, vec![]);
+
+        assert!(state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_DOWN)).consumed);
+        assert!(!state.handle_key_action(&KeyAction::new(*KEY_LEFT_SHIFT, TYPE_UP)).consumed);
+    }
+}
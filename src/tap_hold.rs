@@ -0,0 +1,218 @@
+use crate::*;
+
+pub const DEFAULT_TAPPING_TERM: time::Duration = time::Duration::from_millis(200);
+
+/// A dual-role key definition, the key produces `tap` when clicked and acts as `hold` while held down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapHold {
+    pub tap: KeyClickActionWithMods,
+    pub hold: KeyClickActionWithMods,
+    pub tapping_term: time::Duration,
+}
+
+impl TapHold {
+    fn tap_actions(&self) -> Vec<KeyAction> { self.tap.to_key_actions() }
+
+    fn hold_down_actions(&self) -> Vec<KeyAction> { self.hold.down_actions() }
+
+    fn hold_up_actions(&self) -> Vec<KeyAction> { self.hold.up_actions() }
+}
+
+struct ActiveTapHold {
+    id: usize,
+    key: Key,
+    tap_hold: TapHold,
+    pressed_at: time::Instant,
+    is_hold: bool,
+}
+
+/// The result of feeding an event to the dual-role key tracker.
+pub struct TapHoldOutcome {
+    /// actions that need to be emitted before processing continues
+    pub actions: Vec<KeyAction>,
+    /// whether the original event was consumed
+    pub consumed: bool,
+    /// a timer that should fire `TapHoldTimeout` with the given id after the duration
+    pub timer: Option<(usize, time::Duration)>,
+}
+
+impl TapHoldOutcome {
+    fn passthrough(actions: Vec<KeyAction>) -> Self { TapHoldOutcome { actions, consumed: false, timer: None } }
+    fn consumed(actions: Vec<KeyAction>) -> Self { TapHoldOutcome { actions, consumed: true, timer: None } }
+}
+
+pub struct TapHoldState {
+    // the dual-role keys along with the id of the mapping that defined them
+    mappings: HashMap<Key, (usize, TapHold)>,
+    active: Vec<ActiveTapHold>,
+    next_id: usize,
+}
+
+impl TapHoldState {
+    pub fn new() -> Self {
+        TapHoldState { mappings: Default::default(), active: vec![], next_id: 0 }
+    }
+
+    pub fn add_mapping(&mut self, id: usize, key: Key, tap_hold: TapHold) {
+        self.mappings.insert(key, (id, tap_hold));
+    }
+
+    /// Removes the dual-role key defined by the mapping, keys that are held down are still released
+    /// through it.
+    pub fn remove_id(&mut self, id: usize) -> bool {
+        let len = self.mappings.len();
+        self.mappings.retain(|_, (mapping_id, _)| *mapping_id != id);
+        self.mappings.len() != len
+    }
+
+    pub fn clear(&mut self) { self.mappings.clear(); }
+
+    /// Decides every pending dual-role key as being held, this happens when another key is pressed
+    /// while the dual-role key is down.
+    fn resolve_pending_as_hold(&mut self) -> Vec<KeyAction> {
+        let mut actions = vec![];
+        for active in self.active.iter_mut().filter(|v| !v.is_hold) {
+            active.is_hold = true;
+            actions.append(&mut active.tap_hold.hold_down_actions());
+        }
+        actions
+    }
+
+    pub fn handle_key_action(&mut self, action: &KeyAction, now: time::Instant) -> TapHoldOutcome {
+        if action.value == TYPE_DOWN {
+            let tap_hold = match self.mappings.get(&action.key) {
+                Some((_, tap_hold)) => tap_hold.clone(),
+                // any other key press decides pending dual-role keys
+                None => return TapHoldOutcome::passthrough(self.resolve_pending_as_hold()),
+            };
+
+            let actions = self.resolve_pending_as_hold();
+
+            let id = self.next_id;
+            self.next_id += 1;
+            let tapping_term = tap_hold.tapping_term;
+            self.active.push(ActiveTapHold { id, key: action.key, tap_hold, pressed_at: now, is_hold: false });
+
+            return TapHoldOutcome { actions, consumed: true, timer: Some((id, tapping_term)) };
+        }
+
+        if action.value == TYPE_UP {
+            let active = match self.active.iter().position(|v| v.key == action.key) {
+                Some(idx) => self.active.remove(idx),
+                // the key was pressed before the mapping existed
+                None => return TapHoldOutcome::passthrough(vec![]),
+            };
+
+            if active.is_hold {
+                return TapHoldOutcome::consumed(active.tap_hold.hold_up_actions());
+            }
+
+            if now.duration_since(active.pressed_at) < active.tap_hold.tapping_term {
+                return TapHoldOutcome::consumed(active.tap_hold.tap_actions());
+            }
+
+            let mut actions = active.tap_hold.hold_down_actions();
+            actions.append(&mut active.tap_hold.hold_up_actions());
+            return TapHoldOutcome::consumed(actions);
+        }
+
+        // repeat events of dual-role keys are never forwarded
+        if self.active.iter().any(|v| v.key == action.key) { return TapHoldOutcome::consumed(vec![]); }
+        TapHoldOutcome::passthrough(vec![])
+    }
+
+    /// Called once the tapping term of a dual-role key expires.
+    pub fn handle_timeout(&mut self, id: usize) -> Vec<KeyAction> {
+        match self.active.iter_mut().find(|v| v.id == id && !v.is_hold) {
+            Some(active) => {
+                active.is_hold = true;
+                active.tap_hold.hold_down_actions()
+            }
+            None => vec![],
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> TapHoldState {
+        let mut state = TapHoldState::new();
+        state.add_mapping(0, *KEY_CAPSLOCK, TapHold {
+            tap: KeyClickActionWithMods::new(*KEY_ESC),
+            hold: KeyClickActionWithMods::new(*KEY_LEFT_CTRL),
+            tapping_term: DEFAULT_TAPPING_TERM,
+        });
+        state
+    }
+
+    #[test]
+    fn test_tap() {
+        let mut state = state();
+        let now = time::Instant::now();
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_DOWN), now);
+        assert!(res.consumed);
+        assert_eq!(res.actions, vec![]);
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_UP), now + time::Duration::from_millis(50));
+        assert!(res.consumed);
+        assert_eq!(res.actions, vec![KeyAction::new(*KEY_ESC, TYPE_DOWN), KeyAction::new(*KEY_ESC, TYPE_UP)]);
+    }
+
+    #[test]
+    fn test_hold_with_other_key() {
+        let mut state = state();
+        let now = time::Instant::now();
+
+        state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_DOWN), now);
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_C, TYPE_DOWN), now);
+        assert!(!res.consumed);
+        assert_eq!(res.actions, vec![KeyAction::new(*KEY_LEFT_CTRL, TYPE_DOWN)]);
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_C, TYPE_UP), now);
+        assert!(!res.consumed);
+        assert_eq!(res.actions, vec![]);
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_UP), now);
+        assert!(res.consumed);
+        assert_eq!(res.actions, vec![KeyAction::new(*KEY_LEFT_CTRL, TYPE_UP)]);
+    }
+
+    #[test]
+    fn test_hold_timeout() {
+        let mut state = state();
+        let now = time::Instant::now();
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_DOWN), now);
+        let (id, _) = res.timer.unwrap();
+
+        assert_eq!(state.handle_timeout(id), vec![KeyAction::new(*KEY_LEFT_CTRL, TYPE_DOWN)]);
+        assert_eq!(state.handle_timeout(id), vec![]);
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_UP), now);
+        assert_eq!(res.actions, vec![KeyAction::new(*KEY_LEFT_CTRL, TYPE_UP)]);
+    }
+
+    #[test]
+    fn test_remove_while_held() {
+        let mut state = state();
+        let now = time::Instant::now();
+
+        let (id, _) = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_DOWN), now).timer.unwrap();
+        state.handle_timeout(id);
+        assert!(state.remove_id(0));
+        assert!(!state.remove_id(0));
+
+        // the held key is still released through the removed mapping
+        let res = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_UP), now);
+        assert!(res.consumed);
+        assert_eq!(res.actions, vec![KeyAction::new(*KEY_LEFT_CTRL, TYPE_UP)]);
+
+        let res = state.handle_key_action(&KeyAction::new(*KEY_CAPSLOCK, TYPE_DOWN), now);
+        assert!(!res.consumed);
+    }
+}
@@ -1,19 +1,23 @@
 use crate::*;
-use messaging::*;
 use crate::cli::Configuration;
 
 #[derive(Default)]
 pub struct ScriptTestingParameters<'a> {
     pub script_path: &'a str,
+    /// Defaults to continuing so tests can observe what happens after an error.
+    pub on_error: Option<ErrorPolicy>,
 }
 
 pub struct ScriptTestingAPI {
     pub event_delay: Option<u64>,
 
     ev_reader_tx: mpsc::Sender<InputEvent>,
+    window_ev_tx: mpsc::Sender<ActiveWindowInfo>,
     ev_writer_rx: mpsc::Receiver<InputEvent>,
     stop_tx: futures_intrusive::channel::shared::Sender<()>,
     stdout: Arc<tokio::sync::Mutex<Vec<u8>>>,
+    stderr: Arc<tokio::sync::Mutex<Vec<u8>>>,
+    exit_code: Arc<tokio::sync::Mutex<Option<i32>>>,
 }
 
 impl ScriptTestingAPI {
@@ -38,6 +42,13 @@ impl ScriptTestingAPI {
         self.write_event(action.to_input_ev()).await
     }
 
+    #[allow(unused)]
+    pub async fn focus_window(&mut self, class: &str, name: &str) -> Result<()> {
+        let window = ActiveWindowInfo { class: class.to_string(), instance: class.to_string(), name: name.to_string() };
+        self.window_ev_tx.send(window).await.map_err(|_| anyhow!("failed to send window change"))?;
+        Ok(())
+    }
+
     pub async fn collect_output_ev(&mut self) -> Vec<InputEvent> {
         let mut vec = vec![];
         while let Ok(ev) = self.ev_writer_rx.try_recv() {
@@ -55,6 +66,18 @@ impl ScriptTestingAPI {
 
     #[allow(unused)]
     pub async fn reset_stdout(&mut self) { self.stdout.lock().await.clear(); }
+
+    #[allow(unused)]
+    pub async fn collect_stderr(&mut self) -> String {
+        let mut stderr = self.stderr.lock().await;
+        let result = String::from_utf8_lossy(&stderr).into_owned();
+        stderr.clear();
+        result
+    }
+
+    /// The exit code the script terminated with, `None` if it's still running.
+    #[allow(unused)]
+    pub async fn exit_code(&self) -> Option<i32> { *self.exit_code.lock().await }
 }
 
 pub async fn test_script(
@@ -62,39 +85,57 @@ pub async fn test_script(
 ) -> Result<ScriptTestingAPI> {
     let mut config = Configuration {
         script_file: fs::File::open(parameters.script_path)?,
+        script_path: parameters.script_path.to_string(),
         verbosity: 0,
         devices: vec![],
+        on_error: parameters.on_error.unwrap_or(ErrorPolicy::Continue),
     };
 
-    let script_ast = script::parse_script(&mut config.script_file);
+    let script_ast = script::parse_script(&mut config.script_file, &config.script_path)?;
 
     let mut state = State::new();
-    let window_cycle_token: usize = 0;
+    let mut window_cycle_token: usize = 0;
     let mut mappings = CompiledKeyMappings::new();
     let mut window_change_handlers = vec![];
     let stdout = Arc::new(tokio::sync::Mutex::new(vec![]));
+    let stderr = Arc::new(tokio::sync::Mutex::new(vec![]));
+    let exit_code = Arc::new(tokio::sync::Mutex::new(None));
 
     let (execution_message_tx, mut execution_message_rx) = mpsc::channel(128);
     let (ev_reader_tx, mut ev_reader_rx) = mpsc::channel(128);
+    let (window_ev_tx, mut window_ev_rx) = mpsc::channel(128);
     let (mut ev_writer_tx, ev_writer_rx) = mpsc::channel(128);
 
     let (stop_tx, stop_rx) = futures_intrusive::channel::shared::unbuffered_channel();
     {
         let mut execution_message_tx = execution_message_tx.clone();
         let stdout = stdout.clone();
+        let stderr = stderr.clone();
+        let exit_code = exit_code.clone();
+        let mut window_ev_writer_tx = ev_writer_tx.clone();
         task::spawn(async move {
             loop {
                 tokio::select! {
+                        Some(window) = window_ev_rx.recv() => {
+                            state.active_window = Some(window);
+                            window_cycle_token = window_cycle_token + 1;
+                            event_handlers::handle_active_window_change(&mut window_ev_writer_tx,
+                                &mut execution_message_tx, window_cycle_token, &mut window_change_handlers);
+                        }
                         Some(ev) = ev_reader_rx.recv() => {
                             event_handlers::handle_stdin_ev(&mut state, ev, &mut mappings,
                                 &mut ev_writer_tx, &mut execution_message_tx, window_cycle_token, &config).await.unwrap();
                         }
                         Some(msg) = execution_message_rx.recv() => {
-                            // don't terminate during testing
-                            if let ExecutionMessage::Exit(_) = msg{ return; }
-
-                            event_handlers::handle_execution_message(&mut *stdout.lock().await, window_cycle_token, msg, &mut state,
-                                &mut mappings, &mut window_change_handlers).await;
+                            let code = event_handlers::handle_execution_message(&mut *stdout.lock().await,
+                                &mut *stderr.lock().await, window_cycle_token, msg, &mut state, &mut mappings,
+                                &mut window_change_handlers, &mut ev_writer_tx, &mut execution_message_tx, &config).await;
+
+                            // don't terminate during testing, only stop processing events
+                            if code.is_some() {
+                                *exit_code.lock().await = code;
+                                return;
+                            }
                         }
                         Some(_) = stop_rx.receive() => {
                             return;
@@ -108,9 +149,12 @@ pub async fn test_script(
 
     let api = ScriptTestingAPI {
         ev_reader_tx,
+        window_ev_tx,
         ev_writer_rx,
         stop_tx,
         stdout,
+        stderr,
+        exit_code,
         event_delay: None,
     };
 
@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::*;
+
+static NEXT_TIMER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a new handle for a timer created by `set_timeout` or `set_interval`.
+pub fn next_timer_id() -> usize { NEXT_TIMER_ID.fetch_add(1, Ordering::Relaxed) }
+
+/// A callback that runs after a delay, interval timers run repeatedly until they are cleared.
+#[derive(Clone, Debug)]
+pub struct Timer {
+    pub block: Block,
+    pub var_map: Environment,
+    pub delay: time::Duration,
+    pub repeat: bool,
+}
+
+/// The timers that are waiting to fire, keyed by their handle.
+pub struct TimerState {
+    timers: HashMap<usize, Timer>,
+}
+
+impl TimerState {
+    pub fn new() -> Self { TimerState { timers: HashMap::new() } }
+
+    pub fn add(&mut self, id: usize, timer: Timer) { self.timers.insert(id, timer); }
+
+    /// Returns false if the timer already fired or was cleared.
+    pub fn clear(&mut self, id: usize) -> bool { self.timers.remove(&id).is_some() }
+
+    /// Returns the timer whose delay elapsed, unless it was cleared in the meantime. Timeouts are
+    /// removed, intervals stay until they are cleared and need to be scheduled again.
+    pub fn fire(&mut self, id: usize) -> Option<Timer> {
+        let timer = self.timers.get(&id)?.clone();
+        if !timer.repeat { self.timers.remove(&id); }
+        Some(timer)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer(repeat: bool) -> Timer {
+        Timer { block: Block::new(), var_map: Environment::new(), delay: time::Duration::from_millis(10), repeat }
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut state = TimerState::new();
+        state.add(1, timer(false));
+
+        assert!(state.fire(1).is_some());
+        assert!(state.fire(1).is_none());
+        assert!(!state.clear(1));
+    }
+
+    #[test]
+    fn test_interval() {
+        let mut state = TimerState::new();
+        state.add(1, timer(true));
+
+        assert!(state.fire(1).is_some());
+        assert!(state.fire(1).is_some());
+        assert!(state.clear(1));
+        assert!(state.fire(1).is_none());
+    }
+}
@@ -0,0 +1,73 @@
+use regex::Regex;
+
+use crate::*;
+
+/// Matches a window property, strings need to match exactly while regular expressions may match any part.
+#[derive(Debug, Clone)]
+pub enum WindowPattern {
+    Exact(String),
+    Regex(Regex),
+}
+
+impl PartialEq for WindowPattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (WindowPattern::Exact(l), WindowPattern::Exact(r)) => l == r,
+            (WindowPattern::Regex(l), WindowPattern::Regex(r)) => l.as_str() == r.as_str(),
+            (_, _) => false,
+        }
+    }
+}
+
+impl WindowPattern {
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            WindowPattern::Exact(v) => v == value,
+            WindowPattern::Regex(v) => v.is_match(value),
+        }
+    }
+}
+
+/// The windows that a `when_window` block applies to, unset properties match any window.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WindowCondition {
+    pub class: Option<WindowPattern>,
+    pub title: Option<WindowPattern>,
+}
+
+impl WindowCondition {
+    pub fn matches(&self, window: Option<&ActiveWindowInfo>) -> bool {
+        let window = match window {
+            Some(window) => window,
+            None => return false,
+        };
+
+        self.class.as_ref().map_or(true, |v| v.matches(&window.class)) &&
+            self.title.as_ref().map_or(true, |v| v.matches(&window.name))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(class: &str, name: &str) -> ActiveWindowInfo {
+        ActiveWindowInfo { class: class.to_string(), instance: class.to_string(), name: name.to_string() }
+    }
+
+    #[test]
+    fn test_window_condition() {
+        let condition = WindowCondition {
+            class: Some(WindowPattern::Exact("firefox".to_string())),
+            title: Some(WindowPattern::Regex(Regex::new("GitHub").unwrap())),
+        };
+
+        assert!(condition.matches(Some(&window("firefox", "Pull requests · GitHub"))));
+        assert!(!condition.matches(Some(&window("firefox", "Mozilla"))));
+        assert!(!condition.matches(Some(&window("firefox-dev", "GitHub"))));
+        assert!(!condition.matches(None));
+
+        assert!(WindowCondition::default().matches(Some(&window("kitty", ""))));
+    }
+}